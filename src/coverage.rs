@@ -0,0 +1,348 @@
+use std::{collections::BTreeMap, io::Write, path::Path};
+
+use anyhow::Result;
+
+use crate::{
+    interpreter::{Interpreter, hook::Hook},
+    parser::node::{
+        expression::Expression,
+        statement::{Parameter, Statement},
+    },
+    token::TokenPosition,
+};
+
+/// Found and hit counts of a script, as written in the LCOV summary records.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub lines_found: usize,
+    pub lines_hit: usize,
+    pub branches_found: usize,
+    pub branches_hit: usize,
+}
+
+/// Records which lines ran and which branches of every `if` and logical operator were taken.
+/// Every line and branch of the script is known up front, so the ones that never ran show up
+/// in the report with no hits.
+#[derive(Debug, Default, Clone)]
+pub struct Coverage {
+    lines: BTreeMap<usize, usize>,
+    /// Hits of the taken and not taken arm of each branch, keyed by the `if` keyword or the
+    /// logical operator.
+    branches: BTreeMap<TokenPosition, [usize; 2]>,
+}
+
+impl Coverage {
+    pub fn new(statements: &[Statement]) -> Self {
+        let mut coverage = Self::default();
+        for statement in statements {
+            coverage.register_statement(statement);
+        }
+        coverage
+    }
+
+    fn register_statement(&mut self, statement: &Statement) {
+        if let Some(position) = statement.position() {
+            self.lines.entry(position.row).or_default();
+        }
+
+        match statement {
+            Statement::Block { statements } => {
+                for statement in statements {
+                    self.register_statement(statement);
+                }
+            }
+            Statement::Class {
+                superclass,
+                traits,
+                body,
+                ..
+            } => {
+                if let Some(superclass) = superclass {
+                    self.register_expression(superclass);
+                }
+                for definition in traits {
+                    self.register_expression(definition);
+                }
+                // Methods are never executed as declarations, only their bodies run.
+                for member in body.members() {
+                    match member {
+                        Statement::Function { params, body, .. } => {
+                            self.register_function(params, body)
+                        }
+                        Statement::Var {
+                            initializer: Some(initializer),
+                            ..
+                        } => self.register_expression(initializer),
+                        _ => {}
+                    }
+                }
+            }
+            Statement::Trait { methods, .. } => {
+                for method in methods {
+                    if let Statement::Function { params, body, .. } = method {
+                        self.register_function(params, body);
+                    }
+                }
+            }
+            Statement::Expression { expression } => self.register_expression(expression),
+            Statement::Function { params, body, .. } => self.register_function(params, body),
+            Statement::If {
+                keyword,
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.branches
+                    .entry(keyword.position.to_owned())
+                    .or_default();
+                self.register_expression(condition);
+                self.register_statement(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.register_statement(else_branch);
+                }
+            }
+            Statement::Return { value, .. } => {
+                if let Some(value) = value {
+                    self.register_expression(value);
+                }
+            }
+            Statement::Var { initializer, .. } => {
+                if let Some(initializer) = initializer {
+                    self.register_expression(initializer);
+                }
+            }
+            Statement::While {
+                condition, body, ..
+            } => {
+                self.register_expression(condition);
+                self.register_statement(body);
+            }
+        }
+    }
+
+    fn register_function(&mut self, params: &[Parameter], body: &Statement) {
+        for default in params.iter().filter_map(|param| param.default.as_ref()) {
+            self.register_expression(default);
+        }
+        self.register_statement(body);
+    }
+
+    fn register_expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Logical {
+                left,
+                operator,
+                right,
+            } => {
+                self.branches
+                    .entry(operator.position.to_owned())
+                    .or_default();
+                self.register_expression(left);
+                self.register_expression(right);
+            }
+            Expression::Assign { value, .. } => self.register_expression(value),
+            Expression::Binary { left, right, .. } => {
+                self.register_expression(left);
+                self.register_expression(right);
+            }
+            Expression::Call {
+                callee,
+                arguments,
+                named,
+                ..
+            } => {
+                self.register_expression(callee);
+                for argument in arguments {
+                    self.register_expression(argument);
+                }
+                for argument in named {
+                    self.register_expression(&argument.value);
+                }
+            }
+            Expression::Compound { target, value, .. } => {
+                self.register_expression(target);
+                self.register_expression(value);
+            }
+            Expression::Get { object, .. } => self.register_expression(object),
+            Expression::Grouping { expression } => self.register_expression(expression),
+            Expression::Index { object, index, .. } => {
+                self.register_expression(object);
+                self.register_expression(index);
+            }
+            Expression::SetIndex {
+                object,
+                index,
+                value,
+                ..
+            } => {
+                self.register_expression(object);
+                self.register_expression(index);
+                self.register_expression(value);
+            }
+            Expression::Set { object, value, .. } => {
+                self.register_expression(object);
+                self.register_expression(value);
+            }
+            Expression::Unary { right, .. } => self.register_expression(right),
+            Expression::Lambda { params, body, .. } => self.register_function(params, body),
+            Expression::Literal { .. }
+            | Expression::Super { .. }
+            | Expression::This { .. }
+            | Expression::Variable { .. } => {}
+        }
+    }
+
+    pub fn summary(&self) -> Summary {
+        Summary {
+            lines_found: self.lines.len(),
+            lines_hit: self.lines.values().filter(|hits| **hits > 0).count(),
+            branches_found: self.branches.len() * 2,
+            branches_hit: self
+                .branches
+                .values()
+                .flatten()
+                .filter(|hits| **hits > 0)
+                .count(),
+        }
+    }
+
+    /// Writes the record of `source` in the LCOV tracefile format.
+    pub fn write_lcov(&self, source: &Path, out: &mut impl Write) -> Result<()> {
+        writeln!(out, "TN:")?;
+        writeln!(out, "SF:{}", source.display())?;
+
+        for (block, (site, arms)) in self.branches.iter().enumerate() {
+            for (branch, hits) in arms.iter().enumerate() {
+                // A branch whose condition never ran is reported as `-` rather than 0.
+                let taken = match arms.iter().sum::<usize>() {
+                    0 => "-".to_string(),
+                    _ => hits.to_string(),
+                };
+                writeln!(out, "BRDA:{},{},{},{}", site.row, block, branch, taken)?;
+            }
+        }
+
+        let summary = self.summary();
+        writeln!(out, "BRF:{}", summary.branches_found)?;
+        writeln!(out, "BRH:{}", summary.branches_hit)?;
+
+        for (line, hits) in &self.lines {
+            writeln!(out, "DA:{},{}", line, hits)?;
+        }
+        writeln!(out, "LF:{}", summary.lines_found)?;
+        writeln!(out, "LH:{}", summary.lines_hit)?;
+        writeln!(out, "end_of_record")?;
+
+        Ok(())
+    }
+}
+
+impl Summary {
+    /// Writes the header of the terminal summary table.
+    pub fn write_header(out: &mut impl Write) -> Result<()> {
+        writeln!(out, "{:<40} {:>20} {:>20}", "File", "Lines", "Branches")?;
+        Ok(())
+    }
+
+    pub fn write_row(&self, name: &str, out: &mut impl Write) -> Result<()> {
+        writeln!(
+            out,
+            "{:<40} {:>20} {:>20}",
+            name,
+            Self::ratio(self.lines_hit, self.lines_found),
+            Self::ratio(self.branches_hit, self.branches_found)
+        )?;
+        Ok(())
+    }
+
+    fn ratio(hit: usize, found: usize) -> String {
+        match found {
+            0 => "-".to_string(),
+            _ => format!(
+                "{:.1}% ({}/{})",
+                hit as f64 * 100.0 / found as f64,
+                hit,
+                found
+            ),
+        }
+    }
+}
+
+impl Hook for Coverage {
+    fn on_statement(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        statement: &Statement,
+    ) -> Result<()> {
+        if let Some(position) = statement.position() {
+            *self.lines.entry(position.row).or_default() += 1;
+        }
+
+        Ok(())
+    }
+
+    fn on_branch(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        site: &TokenPosition,
+        taken: bool,
+    ) -> Result<()> {
+        let arms = self.branches.entry(site.to_owned()).or_default();
+        match taken {
+            true => arms[0] += 1,
+            false => arms[1] += 1,
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{lexer, parser::Parser};
+
+    use super::*;
+
+    #[test]
+    fn records_lines_and_branches() {
+        let source = "fun sign(n) {
+  if (n < 0) return -1;
+  return 1;
+}
+fun unused() {
+  return nil;
+}
+var a = sign(1) == 1 or sign(2) == 1;
+"
+        .to_string();
+        let mut statements = Parser::new(lexer::run(source).unwrap()).parse();
+
+        let coverage = Rc::new(RefCell::new(Coverage::new(&statements)));
+        let mut interpreter = Interpreter::default();
+        interpreter.attach(Box::new(coverage.clone()));
+        interpreter.interpret(&mut statements).unwrap();
+
+        let coverage = coverage.borrow();
+        assert_eq!(
+            coverage.summary(),
+            Summary {
+                lines_found: 6,
+                lines_hit: 5,
+                branches_found: 4,
+                branches_hit: 2,
+            }
+        );
+
+        let mut lcov = Vec::new();
+        coverage
+            .write_lcov(Path::new("sign.rot"), &mut lcov)
+            .unwrap();
+        let lcov = String::from_utf8(lcov).unwrap();
+        assert!(lcov.starts_with("TN:\nSF:sign.rot\n"));
+        assert!(lcov.contains("BRDA:2,0,0,0\nBRDA:2,0,1,1\n"));
+        assert!(lcov.contains("DA:6,0\n"));
+        assert!(lcov.ends_with("LF:6\nLH:5\nend_of_record\n"));
+    }
+}
@@ -0,0 +1,506 @@
+use std::{
+    cell::RefCell,
+    fs,
+    io::{BufRead, Write},
+    path::PathBuf,
+    rc::Rc,
+};
+
+use anyhow::Result;
+use serde_json::{Value, json};
+
+use crate::{
+    dap::protocol::Connection,
+    debugger::stepper::{Stepper, Stop},
+    interpreter::{Interpreter, hook::Hook, output::Captured},
+    lexer,
+    memory::heap::Handle,
+    parser::{Parser, node::statement::Statement},
+    token::value::TokenValue,
+};
+
+mod protocol;
+
+const THREAD_ID: u64 = 1;
+
+/// What the message loop does once a request was handled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Flow {
+    Wait,
+    Launch,
+    Resume,
+    Disconnect,
+}
+
+/// Container a DAP `variablesReference` points at while execution is paused.
+enum Reference {
+    Environment(usize),
+    Instance(Handle),
+}
+
+/// Debug adapter serving a single script over the Debug Adapter Protocol.
+pub struct Adapter<R, W> {
+    connection: Connection<R, W>,
+    program: Option<PathBuf>,
+    source: String,
+    configured: bool,
+    stepper: Stepper,
+    references: Vec<Reference>,
+    printed: Captured,
+    disconnected: bool,
+}
+
+/// Talks DAP over `input` and `output` until the client disconnects.
+pub fn serve<R, W>(input: R, output: W) -> Result<()>
+where
+    R: BufRead + 'static,
+    W: Write + 'static,
+{
+    let adapter = Rc::new(RefCell::new(Adapter::new(input, output)));
+    if !adapter.borrow_mut().configure()? {
+        return Ok(());
+    }
+
+    let source = adapter.borrow().source.to_owned();
+    let mut interpreter = Interpreter::default();
+    interpreter.set_output(Box::new(adapter.borrow().printed.clone()));
+    interpreter.attach(Box::new(adapter.clone()));
+
+    let result = launch(&mut interpreter, source);
+    interpreter.detach();
+
+    adapter.borrow_mut().terminate(result)
+}
+
+fn launch(interpreter: &mut Interpreter, source: String) -> Result<()> {
+    let tokens = lexer::run(source)?;
+    let (mut statements, errors) = Parser::new(tokens).parse_with_errors();
+    if let Some(e) = errors.into_iter().next() {
+        return Err(e);
+    }
+
+    interpreter.interpret(&mut statements)?;
+    Ok(())
+}
+
+impl<R: BufRead, W: Write> Adapter<R, W> {
+    fn new(input: R, output: W) -> Self {
+        Self {
+            connection: Connection::new(input, output),
+            program: None,
+            source: String::new(),
+            configured: false,
+            stepper: Stepper::new(false),
+            references: Vec::new(),
+            printed: Captured::default(),
+            disconnected: false,
+        }
+    }
+
+    /// Handles the requests preceding the launch, returns `false` if the client left instead.
+    fn configure(&mut self) -> Result<bool> {
+        while let Some(request) = self.connection.receive()? {
+            match self.handle(&request, None)? {
+                Flow::Launch => return Ok(true),
+                Flow::Disconnect => return Ok(false),
+                Flow::Wait | Flow::Resume => {}
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn terminate(&mut self, result: Result<()>) -> Result<()> {
+        self.flush_printed()?;
+
+        if self.disconnected {
+            return Ok(());
+        }
+
+        if let Err(e) = &result {
+            self.connection.event(
+                "output",
+                json!({ "category": "stderr", "output": format!("{}\n", e) }),
+            )?;
+        }
+
+        self.connection.event("terminated", json!({}))?;
+        self.connection.event(
+            "exited",
+            json!({ "exitCode": if result.is_ok() { 0 } else { 1 } }),
+        )?;
+
+        while let Some(request) = self.connection.receive()? {
+            if self.handle(&request, None)? == Flow::Disconnect {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle(&mut self, request: &Value, interpreter: Option<&mut Interpreter>) -> Result<Flow> {
+        let arguments = &request["arguments"];
+        let command = request["command"].as_str().unwrap_or_default();
+
+        match (command, interpreter) {
+            ("initialize", _) => {
+                self.connection.respond(
+                    request,
+                    json!({
+                        "supportsConfigurationDoneRequest": true,
+                        "supportsEvaluateForHovers": true,
+                    }),
+                )?;
+                self.connection.event("initialized", json!({}))?;
+            }
+            ("launch", _) => {
+                let program = PathBuf::from(arguments["program"].as_str().unwrap_or_default());
+                match fs::read_to_string(&program) {
+                    Ok(source) => {
+                        self.source = source;
+                        self.program = Some(program);
+                        let breakpoints = std::mem::take(&mut self.stepper.breakpoints);
+                        self.stepper = Stepper::new(arguments["stopOnEntry"] == json!(true));
+                        self.stepper.breakpoints = breakpoints;
+                        self.connection.respond(request, json!({}))?;
+                        if self.configured {
+                            return Ok(Flow::Launch);
+                        }
+                    }
+                    Err(e) => {
+                        let message = format!("Couldn't read {}: {}", program.display(), e);
+                        self.connection.fail(request, &message)?;
+                    }
+                }
+            }
+            ("setBreakpoints", _) => {
+                let lines: Vec<usize> = arguments["breakpoints"]
+                    .as_array()
+                    .map(|breakpoints| {
+                        breakpoints
+                            .iter()
+                            .filter_map(|breakpoint| breakpoint["line"].as_u64())
+                            .map(|line| line as usize)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                self.stepper.breakpoints = lines.iter().copied().collect();
+
+                let breakpoints: Vec<Value> = lines
+                    .iter()
+                    .map(|line| json!({ "verified": true, "line": line }))
+                    .collect();
+                self.connection
+                    .respond(request, json!({ "breakpoints": breakpoints }))?;
+            }
+            ("configurationDone", _) => {
+                self.configured = true;
+                self.connection.respond(request, json!({}))?;
+                if self.program.is_some() {
+                    return Ok(Flow::Launch);
+                }
+            }
+            ("threads", _) => {
+                self.connection.respond(
+                    request,
+                    json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] }),
+                )?;
+            }
+            ("stackTrace", Some(interpreter)) => {
+                let frames = self.stack_trace(interpreter);
+                self.connection.respond(
+                    request,
+                    json!({ "stackFrames": frames, "totalFrames": frames.len() }),
+                )?;
+            }
+            ("scopes", Some(interpreter)) => {
+                let depth = arguments["frameId"].as_u64().unwrap_or_default() as usize;
+                let scopes = self.scopes(interpreter, depth);
+                self.connection
+                    .respond(request, json!({ "scopes": scopes }))?;
+            }
+            ("variables", Some(interpreter)) => {
+                let reference =
+                    arguments["variablesReference"].as_u64().unwrap_or_default() as usize;
+                let variables = self.variables(interpreter, reference);
+                self.connection
+                    .respond(request, json!({ "variables": variables }))?;
+            }
+            ("evaluate", Some(interpreter)) => {
+                let expression = arguments["expression"].as_str().unwrap_or_default();
+                let depth = arguments["frameId"]
+                    .as_u64()
+                    .map_or(interpreter.frames().len(), |depth| depth as usize);
+                match Self::evaluate(interpreter, depth, expression) {
+                    Ok(value) => {
+                        let body = json!({
+                            "result": interpreter.stringify(&value),
+                            "variablesReference": self.reference_to(&value),
+                        });
+                        self.connection.respond(request, body)?;
+                    }
+                    Err(e) => self.connection.fail(request, &e.to_string())?,
+                }
+            }
+            ("continue", Some(_)) => {
+                self.stepper.resume();
+                self.connection
+                    .respond(request, json!({ "allThreadsContinued": true }))?;
+                return Ok(Flow::Resume);
+            }
+            ("next", Some(interpreter)) => {
+                self.stepper.step_over(interpreter.frames().len());
+                self.connection.respond(request, json!({}))?;
+                return Ok(Flow::Resume);
+            }
+            ("stepIn", Some(_)) => {
+                self.stepper.step_in();
+                self.connection.respond(request, json!({}))?;
+                return Ok(Flow::Resume);
+            }
+            ("stepOut", Some(interpreter)) => {
+                self.stepper.step_out(interpreter.frames().len());
+                self.connection.respond(request, json!({}))?;
+                return Ok(Flow::Resume);
+            }
+            ("disconnect" | "terminate", _) => {
+                self.connection.respond(request, json!({}))?;
+                return Ok(Flow::Disconnect);
+            }
+            (
+                "stackTrace" | "scopes" | "variables" | "evaluate" | "continue" | "next" | "stepIn"
+                | "stepOut",
+                None,
+            ) => self.connection.fail(request, "The program is not paused")?,
+            _ => {
+                let message = format!("Unsupported request '{}'", command);
+                self.connection.fail(request, &message)?;
+            }
+        }
+
+        Ok(Flow::Wait)
+    }
+
+    fn stack_trace(&self, interpreter: &Interpreter) -> Vec<Value> {
+        let source = self.program.as_ref().map(|program| {
+            json!({
+                "name": program.file_name().map(|name| name.to_string_lossy()),
+                "path": program.to_string_lossy(),
+            })
+        });
+
+        (0..=interpreter.frames().len())
+            .rev()
+            .map(|depth| {
+                let name = match depth {
+                    0 => "<script>",
+                    _ => interpreter.frames()[depth - 1].name.as_str(),
+                };
+                let line = self.stepper.lines().get(depth).copied().unwrap_or(1);
+                json!({
+                    "id": depth,
+                    "name": name,
+                    "source": source,
+                    "line": line,
+                    "column": 1,
+                })
+            })
+            .collect()
+    }
+
+    /// Every environment created by the frame at `depth` becomes a scope of its own.
+    fn scopes(&mut self, interpreter: &Interpreter, depth: usize) -> Vec<Value> {
+        let frames = interpreter.frames();
+        let start = match depth {
+            0 => 0,
+            _ => frames
+                .get(depth - 1)
+                .map(|frame| frame.environment)
+                .unwrap_or(0),
+        };
+        let end = frames
+            .get(depth)
+            .map(|frame| frame.environment)
+            .unwrap_or(interpreter.environments().len());
+
+        (start..end)
+            .map(|index| {
+                let name = match (depth, index - start) {
+                    (0, 0) => "Globals".to_string(),
+                    (_, 0) => "Locals".to_string(),
+                    (_, nesting) => format!("Block {}", nesting),
+                };
+                self.references.push(Reference::Environment(index));
+                json!({
+                    "name": name,
+                    "variablesReference": self.references.len(),
+                    "expensive": false,
+                })
+            })
+            .collect()
+    }
+
+    fn variables(&mut self, interpreter: &Interpreter, reference: usize) -> Vec<Value> {
+        let mut entries: Vec<(String, TokenValue)> =
+            match self.references.get(reference.wrapping_sub(1)) {
+                Some(Reference::Environment(index)) => interpreter
+                    .environments()
+                    .get(*index)
+                    .map(|env| {
+                        env.borrow()
+                            .iter()
+                            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                Some(Reference::Instance(instance)) => interpreter
+                    .heap()
+                    .instance(*instance)
+                    .all_fields()
+                    .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                    .collect(),
+                None => Vec::new(),
+            };
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        entries
+            .into_iter()
+            .map(|(name, value)| {
+                json!({
+                    "name": name,
+                    "value": interpreter.stringify(&value),
+                    "type": value.type_name(),
+                    "variablesReference": self.reference_to(&value),
+                })
+            })
+            .collect()
+    }
+
+    /// Instances can be expanded into their fields, anything else is a leaf.
+    fn reference_to(&mut self, value: &TokenValue) -> usize {
+        match value {
+            TokenValue::Instance(instance) => {
+                self.references.push(Reference::Instance(*instance));
+                self.references.len()
+            }
+            _ => 0,
+        }
+    }
+
+    /// Evaluates `source` in the frame at `depth`, the ids `stackTrace` hands out.
+    fn evaluate(interpreter: &mut Interpreter, depth: usize, source: &str) -> Result<TokenValue> {
+        let tokens = lexer::run(source.to_string())?;
+        let mut expr = Parser::new(tokens).parse_expression()?;
+        interpreter.evaluate_in_frame(depth, &mut expr)
+    }
+
+    fn flush_printed(&mut self) -> Result<()> {
+        let printed = self.printed.take();
+        if !printed.is_empty() {
+            self.connection.event(
+                "output",
+                json!({ "category": "stdout", "output": String::from_utf8_lossy(&printed) }),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead, W: Write> Hook for Adapter<R, W> {
+    fn on_statement(&mut self, interpreter: &mut Interpreter, statement: &Statement) -> Result<()> {
+        self.flush_printed()?;
+
+        let Some(position) = statement.position() else {
+            return Ok(());
+        };
+        let Some(stop) = self.stepper.reach(position.row, interpreter.frames().len()) else {
+            return Ok(());
+        };
+
+        let reason = match stop {
+            Stop::Entry => "entry",
+            Stop::Step => "step",
+            Stop::Breakpoint => "breakpoint",
+        };
+        self.connection.event(
+            "stopped",
+            json!({ "reason": reason, "threadId": THREAD_ID, "allThreadsStopped": true }),
+        )?;
+
+        while let Some(request) = self.connection.receive()? {
+            match self.handle(&request, Some(interpreter))? {
+                Flow::Resume => {
+                    self.references.clear();
+                    return Ok(());
+                }
+                Flow::Disconnect => break,
+                Flow::Wait | Flow::Launch => {}
+            }
+        }
+
+        self.disconnected = true;
+        anyhow::bail!("Debugging session ended")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    /// Session recorded from a client, one message per line: the requests it sent and the
+    /// responses and events it received, in order. `${program}` stands for the script path.
+    const TRANSCRIPT: &str = include_str!("fixtures/breakpoint.jsonl");
+    const PROGRAM: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/dap/fixtures/breakpoint.rot"
+    );
+
+    fn frame(messages: &[Value]) -> Vec<u8> {
+        let mut input = Vec::new();
+        for message in messages {
+            let content = message.to_string();
+            write!(
+                input,
+                "Content-Length: {}\r\n\r\n{}",
+                content.len(),
+                content
+            )
+            .unwrap();
+        }
+        input
+    }
+
+    fn unframe(output: &[u8]) -> Vec<Value> {
+        let mut connection = Connection::new(Cursor::new(output.to_vec()), Vec::new());
+        let mut messages = Vec::new();
+        while let Some(message) = connection.receive().unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    #[test]
+    fn replays_recorded_transcript() {
+        let program = json!(PROGRAM).to_string();
+        let (requests, expected): (Vec<Value>, Vec<Value>) = TRANSCRIPT
+            .lines()
+            .map(|line| {
+                let line = line.replace("${program}", program.trim_matches('"'));
+                serde_json::from_str::<Value>(&line).unwrap()
+            })
+            .partition(|message| message["type"] == "request");
+
+        let output = Captured::default();
+        serve(Cursor::new(frame(&requests)), output.clone()).unwrap();
+
+        let messages = unframe(&output.take());
+        for (index, (message, expected)) in messages.iter().zip(&expected).enumerate() {
+            assert_eq!(message, expected, "message {} differs", index + 1);
+        }
+        assert_eq!(messages.len(), expected.len());
+    }
+}
@@ -0,0 +1,67 @@
+use anyhow::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    Break(Option<usize>),
+    Delete(usize),
+    Step,
+    Next,
+    Finish,
+    Continue,
+    Print(String),
+    List,
+    Backtrace,
+    Quit,
+}
+
+pub const HELP: &str = "\
+break [line]    b   Set a breakpoint at the line, or list the breakpoints
+delete <line>   d   Remove the breakpoint at the line
+step            s   Run until the next statement, entering calls
+next            n   Run until the next statement of the current function
+finish              Run until the current function returns
+continue        c   Run until the next breakpoint
+print <expr>    p   Evaluate an expression in the current frame
+list            l   Show the source around the current line
+backtrace       bt  Show the call stack
+quit            q   Stop the script and leave the debugger
+An empty line repeats the previous command.";
+
+impl Command {
+    pub fn parse(line: &str) -> Result<Self> {
+        let line = line.trim();
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+
+        match name {
+            "help" | "h" => Ok(Command::Help),
+            "break" | "b" if argument.is_empty() => Ok(Command::Break(None)),
+            "break" | "b" => Ok(Command::Break(Some(Self::line(argument)?))),
+            "delete" | "d" => Ok(Command::Delete(Self::line(argument)?)),
+            "step" | "s" => Ok(Command::Step),
+            "next" | "n" => Ok(Command::Next),
+            "finish" => Ok(Command::Finish),
+            "continue" | "c" => Ok(Command::Continue),
+            "print" | "p" if argument.is_empty() => {
+                anyhow::bail!("Command '{}' expects an expression", name)
+            }
+            "print" | "p" => Ok(Command::Print(argument.to_owned())),
+            "list" | "l" => Ok(Command::List),
+            "backtrace" | "bt" => Ok(Command::Backtrace),
+            "quit" | "q" => Ok(Command::Quit),
+            _ => Err(anyhow::anyhow!(
+                "Unknown command '{}', type help for the list of commands",
+                name
+            )),
+        }
+    }
+
+    fn line(argument: &str) -> Result<usize> {
+        argument
+            .parse()
+            .map_err(|_| anyhow::anyhow!("Expected a line number but got '{}'", argument))
+    }
+}
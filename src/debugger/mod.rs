@@ -0,0 +1,269 @@
+use std::io::{BufRead, Write};
+
+use anyhow::Result;
+
+use crate::{
+    debugger::{
+        command::{Command, HELP},
+        stepper::Stepper,
+    },
+    interpreter::{Interpreter, hook::Hook},
+    lexer,
+    parser::{Parser, node::statement::Statement},
+    token::value::TokenValue,
+};
+
+mod command;
+pub mod stepper;
+
+const LIST_RADIUS: usize = 5;
+
+/// Command line debugger driving a script through the interpreter hooks.
+pub struct Debugger<R, W> {
+    source: Vec<String>,
+    input: R,
+    output: W,
+    stepper: Stepper,
+    last_command: Option<Command>,
+}
+
+impl<R: BufRead, W: Write> Debugger<R, W> {
+    pub fn new(source: &str, input: R, output: W) -> Self {
+        Self {
+            source: source.lines().map(|line| line.to_owned()).collect(),
+            input,
+            output,
+            stepper: Stepper::new(true),
+            last_command: None,
+        }
+    }
+
+    fn prompt(&mut self, interpreter: &mut Interpreter) -> Result<()> {
+        loop {
+            write!(self.output, "(rdb) ")?;
+            self.output.flush()?;
+
+            let mut line = String::new();
+            if self.input.read_line(&mut line)? == 0 {
+                // Without anyone left to ask, let the script run to its end.
+                self.stepper.breakpoints.clear();
+                self.stepper.resume();
+                return Ok(());
+            }
+
+            let command = match (line.trim().is_empty(), &self.last_command) {
+                (true, Some(command)) => command.to_owned(),
+                (true, None) => continue,
+                (false, _) => match Command::parse(&line) {
+                    Ok(command) => command,
+                    Err(e) => {
+                        writeln!(self.output, "{}", e)?;
+                        continue;
+                    }
+                },
+            };
+            self.last_command = Some(command.clone());
+
+            let depth = interpreter.frames().len();
+            match command {
+                Command::Help => writeln!(self.output, "{}", HELP)?,
+                Command::Break(Some(line)) => {
+                    self.stepper.breakpoints.insert(line);
+                    writeln!(self.output, "Breakpoint at line {}", line)?;
+                }
+                Command::Break(None) => {
+                    if self.stepper.breakpoints.is_empty() {
+                        writeln!(self.output, "No breakpoints")?;
+                    }
+                    for line in &self.stepper.breakpoints {
+                        writeln!(self.output, "Breakpoint at line {}", line)?;
+                    }
+                }
+                Command::Delete(line) => {
+                    if self.stepper.breakpoints.remove(&line) {
+                        writeln!(self.output, "Deleted breakpoint at line {}", line)?;
+                    } else {
+                        writeln!(self.output, "No breakpoint at line {}", line)?;
+                    }
+                }
+                Command::Step => {
+                    self.stepper.step_in();
+                    return Ok(());
+                }
+                Command::Next => {
+                    self.stepper.step_over(depth);
+                    return Ok(());
+                }
+                Command::Finish => {
+                    self.stepper.step_out(depth);
+                    return Ok(());
+                }
+                Command::Continue => {
+                    self.stepper.resume();
+                    return Ok(());
+                }
+                Command::Print(source) => match Self::evaluate(interpreter, source) {
+                    Ok(value) => writeln!(self.output, "{}", interpreter.stringify(&value))?,
+                    Err(e) => writeln!(self.output, "{}", e)?,
+                },
+                Command::List => self.list()?,
+                Command::Backtrace => self.backtrace(interpreter)?,
+                Command::Quit => anyhow::bail!("Debugging session ended"),
+            }
+        }
+    }
+
+    fn evaluate(interpreter: &mut Interpreter, source: String) -> Result<TokenValue> {
+        let tokens = lexer::run(source)?;
+        let mut expr = Parser::new(tokens).parse_expression()?;
+        interpreter.evaluate(&mut expr)
+    }
+
+    fn show_line(&mut self, line: usize) -> Result<()> {
+        let marker = match (
+            line == self.stepper.current_line(),
+            self.stepper.breakpoints.contains(&line),
+        ) {
+            (true, _) => "->",
+            (false, true) => " *",
+            (false, false) => "  ",
+        };
+        let text = self
+            .source
+            .get(line - 1)
+            .map(|text| text.as_str())
+            .unwrap_or("");
+        writeln!(self.output, "{} {:>4} | {}", marker, line, text)?;
+
+        Ok(())
+    }
+
+    fn list(&mut self) -> Result<()> {
+        let current = self.stepper.current_line();
+        let first = current.saturating_sub(LIST_RADIUS).max(1);
+        let last = (current + LIST_RADIUS).min(self.source.len());
+        for line in first..=last {
+            self.show_line(line)?;
+        }
+
+        Ok(())
+    }
+
+    fn backtrace(&mut self, interpreter: &Interpreter) -> Result<()> {
+        let names = std::iter::once("<script>")
+            .chain(interpreter.frames().iter().map(|frame| frame.name.as_str()));
+        let frames: Vec<(&str, usize)> = names.zip(self.stepper.lines().iter().copied()).collect();
+
+        for (index, (name, line)) in frames.iter().rev().enumerate() {
+            writeln!(self.output, "#{} {} at line {}", index, name, line)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: BufRead, W: Write> Hook for Debugger<R, W> {
+    fn on_statement(&mut self, interpreter: &mut Interpreter, statement: &Statement) -> Result<()> {
+        let Some(position) = statement.position() else {
+            return Ok(());
+        };
+
+        let depth = interpreter.frames().len();
+        if self.stepper.reach(position.row, depth).is_none() {
+            return Ok(());
+        }
+
+        let name = match interpreter.frames().last() {
+            Some(frame) => frame.name.as_str(),
+            None => "<script>",
+        };
+        writeln!(self.output, "Stopped in {} at line {}", name, position.row)?;
+        self.show_line(position.row)?;
+        self.prompt(interpreter)
+    }
+
+    fn on_return(&mut self, interpreter: &mut Interpreter, value: &TokenValue) -> Result<()> {
+        if self.stepper.is_stepping_out_of(interpreter.frames().len()) {
+            writeln!(self.output, "Returned {}", interpreter.stringify(value))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+    use crate::interpreter::{output::Captured, testing::capture};
+
+    const SCRIPT: &str = "fun square(n) {
+  var result = n * n;
+  return result;
+}
+
+var a = square(3);
+var b = square(a);
+";
+
+    fn debug(commands: &str) -> (Result<String>, String) {
+        let output = Captured::default();
+        let mut interpreter = Interpreter::default();
+        interpreter.attach(Box::new(Debugger::new(
+            SCRIPT,
+            Cursor::new(commands.to_string()),
+            output.clone(),
+        )));
+
+        let result = capture(&mut interpreter, SCRIPT);
+        let text = String::from_utf8(output.take()).unwrap();
+        (result, text)
+    }
+
+    #[test]
+    fn breakpoint_print_and_backtrace() {
+        let (result, output) = debug("break 3\ncontinue\np result + 1\nbt\nc\n");
+        assert!(result.is_ok());
+        assert_eq!(
+            output,
+            "Stopped in <script> at line 1
+->    1 | fun square(n) {
+(rdb) Breakpoint at line 3
+(rdb) Stopped in square at line 3
+->    3 |   return result;
+(rdb) 10
+(rdb) #0 square at line 3
+#1 <script> at line 6
+(rdb) Stopped in square at line 3
+->    3 |   return result;
+(rdb) "
+        );
+    }
+
+    #[test]
+    fn step_over_into_and_out() {
+        let (_, output) = debug("n\nn\ns\n\nfinish\nq\n");
+        assert_eq!(
+            output,
+            "Stopped in <script> at line 1
+->    1 | fun square(n) {
+(rdb) Stopped in <script> at line 6
+->    6 | var a = square(3);
+(rdb) Stopped in <script> at line 7
+->    7 | var b = square(a);
+(rdb) Stopped in square at line 2
+->    2 |   var result = n * n;
+(rdb) Stopped in square at line 3
+->    3 |   return result;
+(rdb) Returned 81
+"
+        );
+    }
+
+    #[test]
+    fn quit_stops_the_script() {
+        let (result, _) = debug("quit\n");
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,91 @@
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Entry,
+    Step,
+    Over(usize),
+    Out(usize),
+    Continue,
+}
+
+/// Why execution paused at a statement.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    Entry,
+    Step,
+    Breakpoint,
+}
+
+/// Breakpoints and stepping state shared by the debugger front-ends. Depths count the call
+/// frames, so the script itself runs at depth 0.
+#[derive(Debug, Clone)]
+pub struct Stepper {
+    pub breakpoints: BTreeSet<usize>,
+    mode: Mode,
+    /// Line of the statement being executed by each frame, the script itself first.
+    lines: Vec<usize>,
+}
+
+impl Stepper {
+    pub fn new(stop_on_entry: bool) -> Self {
+        Self {
+            breakpoints: BTreeSet::new(),
+            mode: match stop_on_entry {
+                true => Mode::Entry,
+                false => Mode::Continue,
+            },
+            lines: Vec::new(),
+        }
+    }
+
+    /// Records that the frame at `depth` reached a statement on `line` and decides whether
+    /// execution has to pause there.
+    pub fn reach(&mut self, line: usize, depth: usize) -> Option<Stop> {
+        self.lines.resize(depth + 1, line);
+        self.lines[depth] = line;
+
+        let stop = match self.mode {
+            Mode::Entry => Some(Stop::Entry),
+            Mode::Step => Some(Stop::Step),
+            Mode::Over(origin) if depth <= origin => Some(Stop::Step),
+            Mode::Out(origin) if depth < origin => Some(Stop::Step),
+            _ if self.breakpoints.contains(&line) => Some(Stop::Breakpoint),
+            _ => None,
+        };
+
+        if stop.is_some() {
+            self.mode = Mode::Continue;
+        }
+        stop
+    }
+
+    pub fn step_in(&mut self) {
+        self.mode = Mode::Step;
+    }
+
+    pub fn step_over(&mut self, depth: usize) {
+        self.mode = Mode::Over(depth);
+    }
+
+    pub fn step_out(&mut self, depth: usize) {
+        self.mode = Mode::Out(depth);
+    }
+
+    pub fn resume(&mut self) {
+        self.mode = Mode::Continue;
+    }
+
+    /// Whether the frame at `depth` returning completes a pending step out.
+    pub fn is_stepping_out_of(&self, depth: usize) -> bool {
+        self.mode == Mode::Out(depth)
+    }
+
+    pub fn lines(&self) -> &[usize] {
+        &self.lines
+    }
+
+    pub fn current_line(&self) -> usize {
+        self.lines.last().copied().unwrap_or(1)
+    }
+}
@@ -6,12 +6,62 @@ use crate::token::{Token, value::TokenValue};
 #[derive(Debug, Clone)]
 pub enum InterpreterErrorMessage {
     Unreachable,
-    UnexpectedValue { is: TokenValue, expect: String },
+    UnexpectedValue {
+        is: Box<TokenValue>,
+        expect: String,
+    },
     DivisionByZero,
-    UndefinedVariable { lexeme: String },
+    UndefinedVariable {
+        lexeme: String,
+    },
+    UndefinedProperty {
+        name: String,
+    },
+    PrivateMember {
+        name: String,
+    },
+    ReadOnlyProperty {
+        name: String,
+    },
+    TraitConflict {
+        method: String,
+        traits: [String; 2],
+    },
+    AbstractClass {
+        class: String,
+        missing: Vec<String>,
+    },
+    IndexOutOfRange {
+        index: f64,
+        length: usize,
+    },
     IsNotCallable,
-    ArgumentMismatch { has: usize, expect: usize },
+    ArgumentMismatch {
+        has: usize,
+        min: usize,
+        max: Option<usize>,
+    },
+    UnknownArgument {
+        name: String,
+    },
+    DuplicateArgument {
+        name: String,
+    },
+    MissingArgument {
+        name: String,
+    },
     MissingBlock,
+    StackOverflow {
+        limit: usize,
+    },
+    StatementLimitExceeded {
+        limit: usize,
+    },
+    Timeout,
+    Cancelled,
+    OutOfMemory {
+        limit: usize,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -28,18 +78,67 @@ impl InterpreterError {
                 format!(
                     "Unexpected value\nis: {}\nexpect: {}",
                     Self::format_token_value(is),
-                    expect.to_string()
+                    expect
                 )
             }
             InterpreterErrorMessage::DivisionByZero => "Attempt to divide by zero".to_string(),
             InterpreterErrorMessage::UndefinedVariable { lexeme } => {
                 format!("Undefined variable '{}'", lexeme)
             }
+            InterpreterErrorMessage::UndefinedProperty { name } => {
+                format!("Undefined property '{}'", name)
+            }
+            InterpreterErrorMessage::PrivateMember { name } => {
+                format!(
+                    "Private member '{}' is only accessible inside its class",
+                    name
+                )
+            }
+            InterpreterErrorMessage::ReadOnlyProperty { name } => {
+                format!("Property '{}' has no setter", name)
+            }
+            InterpreterErrorMessage::TraitConflict { method, traits } => format!(
+                "Method '{}' is provided by both {} and {}, the class has to define it",
+                method, traits[0], traits[1]
+            ),
+            InterpreterErrorMessage::AbstractClass { class, missing } => format!(
+                "Can't instantiate {}, abstract methods not implemented: {}",
+                class,
+                missing.join(", ")
+            ),
+            InterpreterErrorMessage::IndexOutOfRange { index, length } => {
+                format!(
+                    "Index {} is out of range for a list of length {}",
+                    index, length
+                )
+            }
             InterpreterErrorMessage::IsNotCallable => "Is not callable".to_string(),
-            InterpreterErrorMessage::ArgumentMismatch { has, expect } => {
-                format!("Expected {} arguments but got {}", expect, has)
+            InterpreterErrorMessage::ArgumentMismatch { has, min, max } => match max {
+                Some(max) if max == min => format!("Expected {} arguments but got {}", min, has),
+                Some(max) => format!("Expected {} to {} arguments but got {}", min, max, has),
+                None => format!("Expected at least {} arguments but got {}", min, has),
+            },
+            InterpreterErrorMessage::UnknownArgument { name } => {
+                format!("No parameter named '{}'", name)
+            }
+            InterpreterErrorMessage::DuplicateArgument { name } => {
+                format!("Argument '{}' is passed more than once", name)
+            }
+            InterpreterErrorMessage::MissingArgument { name } => {
+                format!("Missing argument '{}'", name)
             }
             InterpreterErrorMessage::MissingBlock => "Missing code block".to_string(),
+            InterpreterErrorMessage::StackOverflow { limit } => {
+                format!("Stack overflow, calls nested deeper than {}", limit)
+            }
+            InterpreterErrorMessage::StatementLimitExceeded { limit } => {
+                format!("Executed more than {} statements", limit)
+            }
+            InterpreterErrorMessage::Timeout => "Execution timed out".to_string(),
+            InterpreterErrorMessage::Cancelled => "Execution cancelled".to_string(),
+            InterpreterErrorMessage::OutOfMemory { limit } => {
+                format!("Out of memory, the script uses more than {} bytes", limit)
+            }
         }
     }
 
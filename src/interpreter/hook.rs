@@ -0,0 +1,82 @@
+use std::{cell::RefCell, rc::Rc};
+
+use anyhow::Result;
+
+use crate::{
+    interpreter::Interpreter,
+    memory::heap::Handle,
+    parser::node::statement::Statement,
+    token::{TokenPosition, value::TokenValue},
+};
+
+/// A function invocation that is currently executing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CallFrame {
+    pub name: String,
+    /// Line the called function was declared on, natives have none.
+    pub line: Option<usize>,
+    pub call_site: Option<TokenPosition>,
+    /// Number of environments that existed before the call, so the ones above belong to it.
+    pub environment: usize,
+    /// Class declaring the called method, whose private members it may use.
+    pub class: Option<Handle>,
+}
+
+/// Observer attached to the interpreter, notified from the statement dispatch and around
+/// every function call. Every notification does nothing by default.
+pub trait Hook {
+    /// Runs before `statement` is executed.
+    fn on_statement(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        _statement: &Statement,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs once the frame of the called function was pushed, before its body runs.
+    fn on_call(&mut self, _interpreter: &mut Interpreter, _arguments: &[TokenValue]) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs once an `if` or a logical operator at `site` picked a branch. `taken` is true when
+    /// the then branch runs or the right operand gets evaluated, false when the else branch
+    /// runs or the operator short-circuits.
+    fn on_branch(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        _site: &TokenPosition,
+        _taken: bool,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Runs when the current frame returns `value`, before the frame is popped.
+    fn on_return(&mut self, _interpreter: &mut Interpreter, _value: &TokenValue) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Lets the owner keep a handle on a hook to read its results once the interpreter is done.
+impl<T: Hook> Hook for Rc<RefCell<T>> {
+    fn on_statement(&mut self, interpreter: &mut Interpreter, statement: &Statement) -> Result<()> {
+        self.borrow_mut().on_statement(interpreter, statement)
+    }
+
+    fn on_call(&mut self, interpreter: &mut Interpreter, arguments: &[TokenValue]) -> Result<()> {
+        self.borrow_mut().on_call(interpreter, arguments)
+    }
+
+    fn on_branch(
+        &mut self,
+        interpreter: &mut Interpreter,
+        site: &TokenPosition,
+        taken: bool,
+    ) -> Result<()> {
+        self.borrow_mut().on_branch(interpreter, site, taken)
+    }
+
+    fn on_return(&mut self, interpreter: &mut Interpreter, value: &TokenValue) -> Result<()> {
+        self.borrow_mut().on_return(interpreter, value)
+    }
+}
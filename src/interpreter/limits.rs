@@ -0,0 +1,150 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Instant,
+};
+
+use crate::interpreter::error::InterpreterErrorMessage;
+
+/// Deepest call nesting allowed by default, well below what overflows the host stack.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
+
+/// Bounds on the work a script may do, so untrusted code can't hang or crash the host.
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Statements a single `interpret` call may execute.
+    pub max_statements: Option<usize>,
+    /// Calls that may be active at the same time.
+    pub max_depth: Option<usize>,
+    /// Approximate bytes the values reachable from the script may take.
+    pub max_memory: Option<usize>,
+    /// Point in time after which execution stops.
+    pub deadline: Option<Instant>,
+    /// Stops execution once set, from another thread for example.
+    pub cancelled: Arc<AtomicBool>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_statements: None,
+            max_depth: Some(DEFAULT_MAX_DEPTH),
+            max_memory: None,
+            deadline: None,
+            cancelled: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl Limits {
+    /// Checked before every statement, `executed` counts the statements run so far.
+    pub fn check_statement(&self, executed: usize) -> Result<(), InterpreterErrorMessage> {
+        let message = if self.cancelled.load(Ordering::Relaxed) {
+            InterpreterErrorMessage::Cancelled
+        } else if let Some(limit) = self.max_statements
+            && executed > limit
+        {
+            InterpreterErrorMessage::StatementLimitExceeded { limit }
+        } else if let Some(deadline) = self.deadline
+            && Instant::now() >= deadline
+        {
+            InterpreterErrorMessage::Timeout
+        } else {
+            return Ok(());
+        };
+
+        Err(message)
+    }
+
+    /// Checked before a call is entered while `depth` calls are already active.
+    pub fn check_depth(&self, depth: usize) -> Result<(), InterpreterErrorMessage> {
+        match self.max_depth {
+            Some(limit) if depth >= limit => Err(InterpreterErrorMessage::StackOverflow { limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::interpreter::{Interpreter, testing::capture};
+
+    /// Runs `source` under `limits` and returns the error that stopped it.
+    fn exceed(limits: Limits, source: &str) -> String {
+        let mut interpreter = Interpreter::default();
+        interpreter.set_limits(limits);
+
+        capture(&mut interpreter, source).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn stops_runaway_loops() {
+        let limits = Limits {
+            max_statements: Some(100),
+            ..Default::default()
+        };
+        assert!(exceed(limits, "while (true) {}").contains("Executed more than 100 statements"));
+
+        let limits = Limits {
+            deadline: Some(Instant::now() + Duration::from_millis(10)),
+            ..Default::default()
+        };
+        assert!(exceed(limits, "while (true) {}").contains("Execution timed out"));
+
+        let limits = Limits::default();
+        limits.cancelled.store(true, Ordering::Relaxed);
+        assert!(exceed(limits, "print(1);").contains("Execution cancelled"));
+    }
+
+    #[test]
+    fn reports_unbounded_recursion() {
+        let limits = Limits {
+            max_depth: Some(50),
+            ..Default::default()
+        };
+        let error = exceed(limits, "fun f(n) { return f(n + 1); }\nf(0);");
+        assert!(error.starts_with("[1:26] Interpreter Error: Stack overflow"));
+    }
+
+    #[test]
+    fn caps_memory() {
+        let limits = Limits {
+            max_memory: Some(64 * 1024),
+            ..Default::default()
+        };
+        let error = exceed(
+            limits,
+            "var text = \"a\";\nwhile (true) text = text + text;",
+        );
+        assert!(error.starts_with("[2:26] Interpreter Error: Out of memory"));
+
+        let mut interpreter = Interpreter::default();
+        let before = interpreter.memory_usage();
+        capture(
+            &mut interpreter,
+            "class Box {}\nvar box = Box();\nbox.label = \"0123456789\";",
+        )
+        .unwrap();
+        assert!(interpreter.memory_usage() > before + 10);
+
+        let limits = Limits {
+            max_memory: Some(1_000_000),
+            ..Default::default()
+        };
+        let error = exceed(
+            limits,
+            "var text = \"a\";
+for (var i = 0; i < 16; i++) text = text + text;
+fun keep(value, previous) { return () => previous; }
+var held = nil;
+for (var i = 0; i < 200; i++) held = keep(text + i, held);
+print(\"survived\");",
+        );
+        assert!(error.contains("Out of memory"));
+    }
+}
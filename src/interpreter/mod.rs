@@ -1,20 +1,35 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+    mem,
+    sync::Arc,
+};
 
 use crate::{
-    interpreter::error::{InterpreterError, InterpreterErrorMessage, ReturnValue},
-    memory::handler::EnvironmentHandler,
+    interpreter::{
+        error::{InterpreterError, InterpreterErrorMessage, ReturnValue},
+        hook::{CallFrame, Hook},
+        limits::Limits,
+    },
+    memory::{
+        handler::{EnvironmentHandler, Scope},
+        heap::{Handle, Heap, Object, value_references},
+        usage::Usage,
+    },
     parser::node::{
         Expression, ExpressionVisitor,
-        statement::{Statement, StatementVisitor},
+        expression::NamedArgument,
+        statement::{ClassBody, Parameter, Statement, StatementVisitor},
     },
     token::{
         Token,
         kind::TokenType,
         value::{
             TokenValue,
-            class::Class,
-            function::{Function, FunctionData},
+            class::{Class, Field},
+            function::{Function, FunctionData, NativeFn},
             instance::Instance,
+            traits::Trait,
         },
     },
 };
@@ -22,56 +37,1389 @@ use crate::{
 use anyhow::Result;
 
 mod error;
+pub mod hook;
+pub mod limits;
+pub mod output;
+mod reflection;
+#[cfg(test)]
+pub(crate) mod testing;
+
+pub struct Interpreter {
+    environment: EnvironmentHandler,
+    frames: Vec<CallFrame>,
+    hook: Option<Box<dyn Hook>>,
+    output: Box<dyn Write>,
+    limits: Limits,
+    /// Statements executed by the current `interpret` call.
+    executed: usize,
+    /// Bytes reachable from the environments when memory was last measured.
+    measured: usize,
+    /// Bytes allocated since memory was last measured.
+    allocated: usize,
+    heap: Heap,
+    /// Objects referenced by values that are held while evaluating something else, so a
+    /// collection doesn't free them.
+    temps: Vec<Handle>,
+    /// Use a method named after the class as its initializer instead of `init`.
+    legacy_init: bool,
+    /// Reading a property no class in the chain declares is an error instead of `nil`.
+    strict: bool,
+    /// Class of the instances `gc_stats` returns, allocated by its first call.
+    stats_class: Option<Handle>,
+}
+
+impl std::fmt::Debug for Interpreter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Interpreter")
+            .field("environment", &self.environment)
+            .field("frames", &self.frames)
+            .field("hook", &self.hook.is_some())
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        let mut environment: EnvironmentHandler = Default::default();
+        environment
+            .define(
+                "print".to_string(),
+                Self::native("print", &["text"], |interpreter, _, args| {
+                    let text = interpreter.display(&args[0], None)?;
+                    writeln!(interpreter.output, "{}", text)?;
+                    Ok(TokenValue::Nil)
+                }),
+            )
+            .unwrap();
+        environment
+            .define(
+                "gc_stats".to_string(),
+                Self::native("gc_stats", &[], |interpreter, _, _| interpreter.gc_stats()),
+            )
+            .unwrap();
+        environment
+            .define(
+                "implements".to_string(),
+                Self::native(
+                    "implements",
+                    &["object", "trait"],
+                    |interpreter, _, args| interpreter.implements(&args[0], &args[1]),
+                ),
+            )
+            .unwrap();
+        for (name, native) in Self::reflection() {
+            environment.define(name.to_string(), native).unwrap();
+        }
+
+        Self {
+            environment,
+            frames: Vec::new(),
+            hook: None,
+            output: Box::new(io::stdout()),
+            limits: Limits::default(),
+            executed: 0,
+            measured: 0,
+            allocated: 0,
+            heap: Heap::default(),
+            temps: Vec::new(),
+            legacy_init: false,
+            strict: false,
+            stats_class: None,
+        }
+    }
+}
+
+impl Interpreter {
+    pub fn globals(&self) -> Vec<(String, TokenValue)> {
+        self.environment.globals()
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.environment.names()
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<TokenValue> {
+        self.environment.lookup(name)
+    }
+
+    pub fn interpret(&mut self, statements: &mut Vec<Statement>) -> Result<Option<TokenValue>> {
+        let mut last_value = None;
+        self.executed = 0;
+
+        for statement in statements {
+            last_value = self.execute(statement)?;
+        }
+
+        Ok(last_value)
+    }
+
+    pub fn evaluate(&mut self, expr: &mut Expression) -> Result<TokenValue> {
+        expr.accept::<Result<TokenValue>>(self)
+    }
+
+    /// Evaluates `expr` seeing the variables of the call frame at `depth`, where 0 is the
+    /// script itself, instead of those of the innermost call.
+    pub fn evaluate_in_frame(&mut self, depth: usize, expr: &mut Expression) -> Result<TokenValue> {
+        let start = depth
+            .checked_sub(1)
+            .and_then(|index| self.frames.get(index))
+            .map_or(1, |frame| frame.environment);
+        let end = self
+            .frames
+            .get(depth)
+            .map_or(usize::MAX, |frame| frame.environment);
+
+        let outer = self.environment.restrict(start..end);
+        let result = self.evaluate(expr);
+        self.environment.restrict(outer);
+
+        result
+    }
+
+    /// Dispatches a statement, giving the attached hook a chance to observe it first.
+    pub fn execute(&mut self, statement: &mut Statement) -> Result<Option<TokenValue>> {
+        self.executed += 1;
+        self.limits
+            .check_statement(self.executed)
+            .map_err(|message| InterpreterError {
+                message,
+                token: None,
+            })?;
+
+        if self.hook.is_some() {
+            self.notify(|hook, interpreter| hook.on_statement(interpreter, statement))?;
+        }
+
+        statement.accept(self)
+    }
+
+    pub fn call_function(
+        &mut self,
+        function: &mut Function,
+        arguments: &[TokenValue],
+        call_site: Option<&Token>,
+    ) -> Result<TokenValue> {
+        self.limits
+            .check_depth(self.frames.len())
+            .map_err(|message| InterpreterError {
+                message,
+                token: call_site.cloned(),
+            })?;
+
+        let arguments = &self.bind(&function.data, arguments, call_site)?;
+
+        self.frames.push(CallFrame {
+            name: function.data.name.to_owned(),
+            line: function.data.line,
+            call_site: call_site.map(|token| token.position.to_owned()),
+            environment: self.environment.environments().len(),
+            class: function.data.class,
+        });
+
+        if self.hook.is_some()
+            && let Err(e) = self.notify(|hook, interpreter| hook.on_call(interpreter, arguments))
+        {
+            self.frames.pop();
+            return Err(e);
+        }
+
+        let mut result = (function.call)(self, &mut function.data, arguments);
+
+        if self.hook.is_some()
+            && let Ok(value) = &result
+            && let Err(e) = self.notify(|hook, interpreter| hook.on_return(interpreter, value))
+        {
+            result = Err(e);
+        }
+
+        self.frames.pop();
+        result
+    }
+
+    /// One argument for every parameter of `data`: those `arguments` leave out take their
+    /// default and a rest parameter gets the ones left over as a list.
+    fn bind(
+        &mut self,
+        data: &FunctionData,
+        arguments: &[TokenValue],
+        call_site: Option<&Token>,
+    ) -> Result<Vec<TokenValue>> {
+        Self::check_arity(data.arity(), arguments.len(), call_site)?;
+
+        let fixed = data.params.len() - usize::from(data.rest);
+        let given = arguments.len().min(fixed);
+        let mut bound = arguments[..given].to_vec();
+        bound.extend_from_slice(&data.defaults[data.defaults.len() - (fixed - given)..]);
+        if data.rest {
+            bound.push(self.list(arguments[given..].to_vec())?);
+        }
+
+        Ok(bound)
+    }
+
+    fn check_arity(
+        (min, max): (usize, Option<usize>),
+        has: usize,
+        call_site: Option<&Token>,
+    ) -> Result<()> {
+        if has < min || max.is_some_and(|max| has > max) {
+            return Err(InterpreterError {
+                message: InterpreterErrorMessage::ArgumentMismatch { has, min, max },
+                token: call_site.cloned(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    /// Puts `named` arguments in the place of the parameters of `data` they name, after the
+    /// `positional` ones. Parameters in between that neither sets take their default.
+    fn arrange(
+        data: &FunctionData,
+        positional: Vec<TokenValue>,
+        named: Vec<(Token, TokenValue)>,
+        call_site: &Token,
+    ) -> Result<Vec<TokenValue>> {
+        let fixed = data.params.len() - usize::from(data.rest);
+        let mut slots: Vec<Option<TokenValue>> = vec![None; fixed.max(positional.len())];
+        for (slot, value) in slots.iter_mut().zip(positional) {
+            *slot = Some(value);
+        }
+
+        for (name, value) in named {
+            let error = |message| -> anyhow::Error {
+                InterpreterError {
+                    message,
+                    token: Some(name.to_owned()),
+                }
+                .into()
+            };
+            let Some(index) = data.params[..fixed]
+                .iter()
+                .position(|param| *param == name.lexeme)
+            else {
+                return Err(error(InterpreterErrorMessage::UnknownArgument {
+                    name: name.lexeme.to_owned(),
+                }));
+            };
+            if slots[index].is_some() {
+                return Err(error(InterpreterErrorMessage::DuplicateArgument {
+                    name: name.lexeme.to_owned(),
+                }));
+            }
+            slots[index] = Some(value);
+        }
+
+        // Trailing parameters left out are completed when the call binds its arguments.
+        while slots.last().is_some_and(Option::is_none) {
+            slots.pop();
+        }
+        let required = fixed - data.defaults.len();
+        slots
+            .into_iter()
+            .enumerate()
+            .map(|(index, slot)| match slot {
+                Some(value) => Ok(value),
+                None if index >= required => Ok(data.defaults[index - required].to_owned()),
+                None => Err(InterpreterError {
+                    message: InterpreterErrorMessage::MissingArgument {
+                        name: data.params[index].to_owned(),
+                    },
+                    token: Some(call_site.to_owned()),
+                }
+                .into()),
+            })
+            .collect()
+    }
+
+    pub fn frames(&self) -> &[CallFrame] {
+        &self.frames
+    }
+
+    /// Name of the function `frame` runs qualified by its class, as in `Point.init`, and
+    /// anonymous functions by the line declaring them, as in `lambda@3`.
+    pub fn qualified_name(&self, frame: &CallFrame) -> String {
+        let name = match frame.line {
+            Some(line) if frame.name == "lambda" => format!("lambda@{}", line),
+            _ => frame.name.to_owned(),
+        };
+        match frame.class {
+            Some(class) => format!("{}.{}", self.heap.class(class).name, name),
+            None => name,
+        }
+    }
+
+    pub fn environments(&self) -> &[Scope] {
+        self.environment.environments()
+    }
+
+    /// Redirects what scripts print, which goes to stdout by default.
+    pub fn set_output(&mut self, output: Box<dyn Write>) {
+        self.output = output;
+    }
+
+    pub fn limits(&self) -> &Limits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Approximate bytes taken by the values the script can still reach.
+    pub fn memory_usage(&self) -> usize {
+        Usage::measure(&self.heap, self.environment.environments())
+    }
+
+    /// Accounts for `bytes` the script is about to allocate. Memory is only measured again
+    /// once the allocations since the last measurement could exceed the budget.
+    pub fn allocate(&mut self, bytes: usize, token: Option<&Token>) -> Result<()> {
+        let Some(limit) = self.limits.max_memory else {
+            return Ok(());
+        };
+
+        self.allocated += bytes;
+        if self.measured + self.allocated <= limit {
+            return Ok(());
+        }
+
+        self.measured = self.memory_usage();
+        self.allocated = bytes;
+        if self.measured + self.allocated > limit {
+            return Err(InterpreterError {
+                message: InterpreterErrorMessage::OutOfMemory { limit },
+                token: token.cloned(),
+            }
+            .into());
+        }
+
+        Ok(())
+    }
+
+    pub fn heap(&self) -> &Heap {
+        &self.heap
+    }
+
+    /// Calls the method named after the class when it is instantiated, as scripts written
+    /// before `init` expect.
+    pub fn set_legacy_init(&mut self, legacy: bool) {
+        self.legacy_init = legacy;
+    }
+
+    /// Makes reading an undeclared property an error, declared fields start as `nil`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    /// Name of the method that initializes instances of the class named `class`.
+    fn initializer_name<'a>(&self, class: &'a str) -> &'a str {
+        if self.legacy_init { class } else { "init" }
+    }
+
+    /// Collects before every allocation, which makes values that are not rooted fail fast.
+    pub fn set_gc_stress(&mut self, stress: bool) {
+        self.heap.set_stress(stress);
+    }
+
+    /// Moves `object` to the heap, collecting first when the heap grew enough.
+    pub fn alloc(&mut self, object: Object) -> Handle {
+        if self.heap.should_collect() {
+            let mut roots = self.roots();
+            roots.extend(object.references());
+            self.heap.collect(roots);
+        }
+
+        self.heap.alloc(object)
+    }
+
+    /// Allocates a list holding `items`.
+    fn list(&mut self, items: Vec<TokenValue>) -> Result<TokenValue> {
+        self.allocate(
+            mem::size_of::<Vec<TokenValue>>() + items.iter().map(Usage::shallow).sum::<usize>(),
+            None,
+        )?;
+        Ok(TokenValue::List(self.alloc(Object::List(items))))
+    }
+
+    /// Frees the objects the script can no longer reach, returns how many.
+    pub fn collect_garbage(&mut self) -> usize {
+        let roots = self.roots();
+        self.heap.collect(roots)
+    }
+
+    /// Objects referenced from the environment stack, which holds the locals of every call
+    /// frame, and from the values held mid-evaluation.
+    fn roots(&self) -> Vec<Handle> {
+        self.environment
+            .environments()
+            .iter()
+            .flat_map(|environment| {
+                environment
+                    .borrow()
+                    .iter()
+                    .flat_map(|(_, value)| value_references(value))
+                    .collect::<Vec<_>>()
+            })
+            .chain(self.frames.iter().filter_map(|frame| frame.class))
+            .chain(self.stats_class)
+            .chain(self.temps.iter().copied())
+            .collect()
+    }
+
+    /// Runs `evaluation` while the object `value` references is kept alive.
+    fn rooted<T>(
+        &mut self,
+        value: &TokenValue,
+        evaluation: impl FnOnce(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let base = self.temps.len();
+        self.temps.extend(value_references(value));
+        let result = evaluation(self);
+        self.temps.truncate(base);
+
+        result
+    }
+
+    /// Default representation of a value, without running any script code. Instances show
+    /// their fields, an instance already being printed shows up as `Name {...}`.
+    pub fn stringify(&self, value: &TokenValue) -> String {
+        self.represent(value, &mut Vec::new())
+    }
+
+    fn represent(&self, value: &TokenValue, printing: &mut Vec<Handle>) -> String {
+        match value {
+            TokenValue::Class(class) => format!("<class {}>", self.heap.class(*class).name),
+            TokenValue::Trait(definition) => {
+                format!("<trait {}>", self.heap.get_trait(*definition).name)
+            }
+            TokenValue::Instance(handle) => {
+                let instance = self.heap.instance(*handle);
+                let name = &self.heap.class(instance.class).name;
+                if printing.contains(handle) {
+                    return format!("{} {{...}}", name);
+                }
+
+                // Private fields stay hidden, only methods of their class may read them.
+                let mut fields: Vec<_> = instance.fields.iter().collect();
+                if fields.is_empty() {
+                    return format!("{} {{}}", name);
+                }
+                fields.sort_by(|a, b| a.0.cmp(b.0));
+
+                printing.push(*handle);
+                let fields: Vec<String> = fields
+                    .into_iter()
+                    .map(|(field, value)| format!("{}: {}", field, self.represent(value, printing)))
+                    .collect();
+                printing.pop();
+
+                format!("{} {{ {} }}", name, fields.join(", "))
+            }
+            TokenValue::List(handle) => {
+                if printing.contains(handle) {
+                    return "[...]".to_string();
+                }
+
+                printing.push(*handle);
+                let items: Vec<String> = self
+                    .heap
+                    .list(*handle)
+                    .iter()
+                    .map(|item| self.represent(item, printing))
+                    .collect();
+                printing.pop();
+
+                format!("[{}]", items.join(", "))
+            }
+            _ => value.to_string(),
+        }
+    }
+
+    /// Text a script sees when a value is printed or concatenated, which calls the
+    /// `toString` method of instances that have one, also inside lists and fields.
+    fn display(&mut self, value: &TokenValue, token: Option<&Token>) -> Result<String> {
+        self.display_nested(value, &mut Vec::new(), token)
+    }
+
+    fn display_nested(
+        &mut self,
+        value: &TokenValue,
+        printing: &mut Vec<Handle>,
+        token: Option<&Token>,
+    ) -> Result<String> {
+        match value {
+            TokenValue::Instance(handle) => {
+                match self.call_special(value, "toString", &[], token)? {
+                    Some(TokenValue::String(text)) => return Ok(text),
+                    Some(text) => return Ok(self.stringify(&text)),
+                    None => {}
+                }
+
+                let instance = self.heap.instance(*handle);
+                let name = self.heap.class(instance.class).name.to_owned();
+                if printing.contains(handle) {
+                    return Ok(format!("{} {{...}}", name));
+                }
+
+                let mut fields: Vec<(String, TokenValue)> = instance
+                    .fields
+                    .iter()
+                    .map(|(field, value)| (field.to_owned(), value.to_owned()))
+                    .collect();
+                if fields.is_empty() {
+                    return Ok(format!("{} {{}}", name));
+                }
+                fields.sort_by(|a, b| a.0.cmp(&b.0));
+
+                printing.push(*handle);
+                let fields = self.rooted(value, |interpreter| {
+                    fields
+                        .iter()
+                        .map(|(field, value)| {
+                            let text = interpreter.display_nested(value, printing, token)?;
+                            Ok(format!("{}: {}", field, text))
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })?;
+                printing.pop();
+
+                Ok(format!("{} {{ {} }}", name, fields.join(", ")))
+            }
+            TokenValue::List(handle) => {
+                if printing.contains(handle) {
+                    return Ok("[...]".to_string());
+                }
+
+                printing.push(*handle);
+                let items = self.heap.list(*handle).to_vec();
+                let items = self.rooted(value, |interpreter| {
+                    items
+                        .iter()
+                        .map(|item| interpreter.display_nested(item, printing, token))
+                        .collect::<Result<Vec<_>>>()
+                })?;
+                printing.pop();
+
+                Ok(format!("[{}]", items.join(", ")))
+            }
+            _ => Ok(self.stringify(value)),
+        }
+    }
+
+    fn gc_stats(&mut self) -> Result<TokenValue> {
+        let stats = self.heap.stats().to_owned();
+        let live = self.heap.len();
+
+        let fields = [
+            ("collections", stats.collections as f64),
+            ("objects_freed", stats.objects_freed as f64),
+            ("bytes_freed", stats.bytes_freed as f64),
+            ("pause_ms", stats.pause.as_secs_f64() * 1000.0),
+            ("live_objects", live as f64),
+        ];
+
+        let class = match self.stats_class {
+            Some(class) => class,
+            None => {
+                let mut class = Class::new("GcStats".to_string(), None);
+                for (name, _) in fields {
+                    class.declare_field(Field {
+                        name: name.to_string(),
+                        initializer: None,
+                    });
+                }
+                let class = self.alloc(Object::Class(Box::new(class)));
+                self.stats_class = Some(class);
+                class
+            }
+        };
+        let mut instance = Instance::new(class);
+        for (name, value) in fields {
+            instance.fields.define(name.to_string(), value.into());
+        }
+
+        Ok(TokenValue::Instance(self.alloc(Object::Instance(instance))))
+    }
+
+    /// Whether the class of `object`, or `object` itself when it is a class, composes
+    /// `definition`.
+    fn implements(&self, object: &TokenValue, definition: &TokenValue) -> Result<TokenValue> {
+        let TokenValue::Trait(definition) = definition else {
+            return Err(InterpreterError {
+                message: InterpreterErrorMessage::UnexpectedValue {
+                    is: Box::new(definition.to_owned()),
+                    expect: "Trait".to_string(),
+                },
+                token: None,
+            }
+            .into());
+        };
+
+        let class = match object {
+            TokenValue::Instance(instance) => self.heap.instance(*instance).class,
+            TokenValue::Class(class) => *class,
+            _ => return Ok(TokenValue::Bool(false)),
+        };
+        Ok(TokenValue::Bool(self.heap.implements(class, *definition)))
+    }
+
+    /// Compares with the `equals` method of an instance's class when it has one, by identity
+    /// for other objects and by value for everything else.
+    fn equals(&mut self, left: &TokenValue, right: &TokenValue, operator: &Token) -> Result<bool> {
+        match self.call_special(left, "equals", std::slice::from_ref(right), Some(operator))? {
+            Some(result) => bool::try_from(result),
+            None => Ok(left == right),
+        }
+    }
+
+    /// Calls the special method `name` of `value` when it is an instance whose class defines
+    /// one, `None` tells the caller to fall back to the built-in behavior.
+    fn call_special(
+        &mut self,
+        value: &TokenValue,
+        name: &str,
+        arguments: &[TokenValue],
+        token: Option<&Token>,
+    ) -> Result<Option<TokenValue>> {
+        let TokenValue::Instance(instance) = value else {
+            return Ok(None);
+        };
+        let class = self.heap.instance(*instance).class;
+        let Some(method) = self.heap.find_method(class, name) else {
+            return Ok(None);
+        };
+
+        self.call_method(*instance, method, arguments, token)
+            .map(Some)
+    }
+
+    /// Copies the methods of `definitions` into `class`, except those its body declares
+    /// itself. A method left that more than one trait provides is ambiguous.
+    fn compose(
+        &mut self,
+        name: &Token,
+        class: Handle,
+        definitions: &[Handle],
+        declared: &[Statement],
+    ) -> Result<()> {
+        let declared: Vec<&str> = declared
+            .iter()
+            .filter_map(|method| match method {
+                Statement::Function { name, .. } => Some(name.lexeme.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        let mut providers: HashMap<String, Handle> = HashMap::new();
+        for definition in definitions {
+            let mut methods: Vec<Function> = self
+                .heap
+                .get_trait(*definition)
+                .methods()
+                .filter(|method| !declared.contains(&method.data.name.as_str()))
+                .cloned()
+                .collect();
+            methods.sort_by(|a, b| a.data.name.cmp(&b.data.name));
+
+            for mut method in methods {
+                if let Some(other) = providers.insert(method.data.name.to_owned(), *definition)
+                    && other != *definition
+                {
+                    return Err(InterpreterError {
+                        message: InterpreterErrorMessage::TraitConflict {
+                            method: method.data.name,
+                            traits: [
+                                self.heap.get_trait(other).name.to_owned(),
+                                self.heap.get_trait(*definition).name.to_owned(),
+                            ],
+                        },
+                        token: Some(name.to_owned()),
+                    }
+                    .into());
+                }
+
+                method.data.class = Some(class);
+                self.heap.class_mut(class).define_method(method);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Function declared in the body of `class`.
+    fn method(&mut self, class: Handle, declaration: &mut Statement) -> Result<Function> {
+        match declaration {
+            Statement::Function { name, params, body } => {
+                let closure = self.heap.class(class).closure;
+                let mut function = self.create_closure(closure, name, params, body)?;
+                function.data.class = Some(class);
+                Ok(function)
+            }
+            _ => Err(InterpreterError {
+                message: InterpreterErrorMessage::IsNotCallable,
+                token: None,
+            }
+            .into()),
+        }
+    }
+
+    /// Binds `method` to `instance` and calls it, keeping both alive meanwhile.
+    fn call_method(
+        &mut self,
+        instance: Handle,
+        mut method: Function,
+        arguments: &[TokenValue],
+        token: Option<&Token>,
+    ) -> Result<TokenValue> {
+        method.data.this = Some(instance);
+
+        let base = self.temps.len();
+        self.temps.push(instance);
+        self.temps
+            .extend(arguments.iter().flat_map(value_references));
+        let result = self.call_function(&mut method, arguments, token);
+        self.temps.truncate(base);
+
+        result
+    }
+
+    /// Dispatches an arithmetic or comparison operator to the special method of the left
+    /// operand. Only `__lt__` is needed for comparisons, the others swap or negate it.
+    fn overloaded(
+        &mut self,
+        left: &TokenValue,
+        operator: &Token,
+        right: &TokenValue,
+    ) -> Result<Option<TokenValue>> {
+        let (receiver, name, argument, negate) = match operator.kind {
+            TokenType::Plus => (left, "__add__", right, None),
+            TokenType::Minus => (left, "__sub__", right, None),
+            TokenType::Star => (left, "__mul__", right, None),
+            TokenType::Slash => (left, "__div__", right, None),
+            TokenType::Percent => (left, "__mod__", right, None),
+            TokenType::Less => (left, "__lt__", right, Some(false)),
+            TokenType::Greater => (right, "__lt__", left, Some(false)),
+            TokenType::LessEqual => (right, "__lt__", left, Some(true)),
+            TokenType::GreaterEqual => (left, "__lt__", right, Some(true)),
+            _ => return Ok(None),
+        };
+
+        let result = self.call_special(
+            receiver,
+            name,
+            std::slice::from_ref(argument),
+            Some(operator),
+        )?;
+        match (result, negate) {
+            (Some(result), Some(negate)) => {
+                Ok(Some(TokenValue::Bool(bool::try_from(result)? != negate)))
+            }
+            (result, _) => Ok(result),
+        }
+    }
+
+    /// Whether `+` joins a string with an instance through its `toString` method, which wins
+    /// over the `__add__` method of the instance.
+    fn concatenates(&self, left: &TokenValue, operator: &Token, right: &TokenValue) -> bool {
+        match (&operator.kind, left, right) {
+            (&TokenType::Plus, TokenValue::String(_), TokenValue::Instance(instance))
+            | (&TokenType::Plus, TokenValue::Instance(instance), TokenValue::String(_)) => {
+                let class = self.heap.instance(*instance).class;
+                self.heap.find_method(class, "toString").is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Applies the binary `operator` to values, dispatching to special methods first.
+    fn operate(
+        &mut self,
+        left_val: TokenValue,
+        operator: &Token,
+        right_val: TokenValue,
+    ) -> Result<TokenValue> {
+        if !self.concatenates(&left_val, operator, &right_val)
+            && let Some(value) = self.overloaded(&left_val, operator, &right_val)?
+        {
+            return Ok(value);
+        }
+
+        match operator.kind {
+            TokenType::Plus => {
+                if let (TokenValue::String(_), _) | (_, TokenValue::String(_)) =
+                    (&left_val, &right_val)
+                {
+                    let left_text = self.rooted(&right_val, |interpreter| {
+                        interpreter.display(&left_val, Some(operator))
+                    })?;
+                    let right_text = self.display(&right_val, Some(operator))?;
+                    let value = TokenValue::String(format!("{}{}", left_text, right_text));
+                    self.allocate(Usage::shallow(&value), Some(operator))?;
+                    return Ok(value);
+                }
+            }
+            TokenType::EqualEqual => {
+                return Ok(TokenValue::Bool(
+                    self.equals(&left_val, &right_val, operator)?,
+                ));
+            }
+            TokenType::BangEqual => {
+                return Ok(TokenValue::Bool(
+                    !self.equals(&left_val, &right_val, operator)?,
+                ));
+            }
+            _ => {}
+        }
+
+        let left_num = self.as_number(left_val, operator)?;
+        let right_num = self.as_number(right_val, operator)?;
+
+        match operator.kind {
+            TokenType::Plus => {
+                return Ok(TokenValue::Number(left_num + right_num));
+            }
+            TokenType::Minus => {
+                return Ok(TokenValue::Number(left_num - right_num));
+            }
+            TokenType::Slash => {
+                if right_num == 0.0 {
+                    return Err(InterpreterError {
+                        message: InterpreterErrorMessage::DivisionByZero,
+                        token: Some(operator.to_owned()),
+                    }
+                    .into());
+                }
+                return Ok(TokenValue::Number(left_num / right_num));
+            }
+            TokenType::Star => {
+                return Ok(TokenValue::Number(left_num * right_num));
+            }
+            // The remainder takes the sign of the divisor, so `-1 % 3` is 2 like `2 % 3`.
+            TokenType::Percent => {
+                if right_num == 0.0 {
+                    return Err(InterpreterError {
+                        message: InterpreterErrorMessage::DivisionByZero,
+                        token: Some(operator.to_owned()),
+                    }
+                    .into());
+                }
+                return Ok(TokenValue::Number(
+                    left_num - right_num * (left_num / right_num).floor(),
+                ));
+            }
+            TokenType::Greater => {
+                return Ok(TokenValue::Bool(left_num > right_num));
+            }
+            TokenType::GreaterEqual => {
+                return Ok(TokenValue::Bool(left_num >= right_num));
+            }
+            TokenType::Less => {
+                return Ok(TokenValue::Bool(left_num < right_num));
+            }
+            TokenType::LessEqual => {
+                return Ok(TokenValue::Bool(left_num <= right_num));
+            }
+            _ => {}
+        }
+
+        Err(InterpreterError {
+            message: InterpreterErrorMessage::Unreachable,
+            token: Some(operator.to_owned()),
+        }
+        .into())
+    }
+
+    /// Reads the property `name` of an instance, through its getter when there is one, or
+    /// a static member of a class.
+    fn get_member(&mut self, object: TokenValue, name: &Token) -> Result<TokenValue> {
+        match object {
+            TokenValue::Instance(instance) => {
+                let class = self.member_class(self.heap.instance(instance).class, name)?;
+                if let Some(getter) = self.heap.find_getter(class, &name.lexeme) {
+                    return self.call_method(instance, getter, &[], Some(name));
+                }
+
+                if self.strict && !self.heap.declares(class, &name.lexeme) {
+                    return Err(InterpreterError {
+                        message: InterpreterErrorMessage::UndefinedProperty {
+                            name: name.lexeme.to_owned(),
+                        },
+                        token: Some(name.to_owned()),
+                    }
+                    .into());
+                }
+
+                Ok(self
+                    .get_property(instance, class, name)
+                    .unwrap_or(TokenValue::Nil))
+            }
+            TokenValue::Class(class) => {
+                let class = self.member_class(class, name)?;
+                Ok(self
+                    .heap
+                    .find_static(class, &name.lexeme)
+                    .unwrap_or(TokenValue::Nil))
+            }
+            _ => Err(InterpreterError {
+                message: InterpreterErrorMessage::UnexpectedValue {
+                    is: Box::new(object),
+                    expect: "Instance or Class".to_string(),
+                },
+                token: Some(name.to_owned()),
+            }
+            .into()),
+        }
+    }
+
+    /// Assigns the property `name` of an instance, through its setter when there is one, or
+    /// a static member of a class.
+    fn set_member(
+        &mut self,
+        object: TokenValue,
+        name: &Token,
+        value: TokenValue,
+    ) -> Result<TokenValue> {
+        match object {
+            TokenValue::Instance(instance) => {
+                let class = self.member_class(self.heap.instance(instance).class, name)?;
+                if let Some(setter) = self.heap.find_setter(class, &name.lexeme) {
+                    self.call_method(instance, setter, std::slice::from_ref(&value), Some(name))?;
+                    return Ok(value);
+                }
+                // A field would never be read, the getter wins over it.
+                if self.heap.find_getter(class, &name.lexeme).is_some() {
+                    return Err(InterpreterError {
+                        message: InterpreterErrorMessage::ReadOnlyProperty {
+                            name: name.lexeme.to_owned(),
+                        },
+                        token: Some(name.to_owned()),
+                    }
+                    .into());
+                }
+
+                self.allocate(name.lexeme.len() + Usage::shallow(&value), Some(name))?;
+                self.heap.instance_mut(instance).set_field(
+                    class,
+                    name.lexeme.to_owned(),
+                    value.clone(),
+                );
+                Ok(value)
+            }
+            TokenValue::Class(class) => {
+                let class = self.member_class(class, name)?;
+                self.allocate(name.lexeme.len() + Usage::shallow(&value), Some(name))?;
+                self.heap
+                    .class_mut(class)
+                    .statics
+                    .define(name.lexeme.to_owned(), value.clone());
+                Ok(value)
+            }
+            _ => Err(InterpreterError {
+                message: InterpreterErrorMessage::UnexpectedValue {
+                    is: Box::new(object),
+                    expect: "Instace".to_string(),
+                },
+                token: Some(name.to_owned()),
+            }
+            .into()),
+        }
+    }
+
+    /// Element of a list, or what the `__index__` method of an instance returns.
+    fn get_index(
+        &mut self,
+        object: TokenValue,
+        bracket: &Token,
+        index: TokenValue,
+    ) -> Result<TokenValue> {
+        if let TokenValue::List(list) = object {
+            let position = self.list_position(list, index, bracket)?;
+            return Ok(self.heap.list(list)[position].to_owned());
+        }
+
+        let value = self.rooted(&object, |interpreter| {
+            interpreter.call_special(
+                &object,
+                "__index__",
+                std::slice::from_ref(&index),
+                Some(bracket),
+            )
+        })?;
+        value.ok_or_else(|| {
+            InterpreterError {
+                message: InterpreterErrorMessage::UnexpectedValue {
+                    is: Box::new(object),
+                    expect: "List or Instance with __index__".to_string(),
+                },
+                token: Some(bracket.to_owned()),
+            }
+            .into()
+        })
+    }
+
+    /// Replaces an element of a list, or passes the index and value to the `__setindex__`
+    /// method of an instance.
+    fn set_index(
+        &mut self,
+        object: TokenValue,
+        bracket: &Token,
+        index: TokenValue,
+        value: TokenValue,
+    ) -> Result<TokenValue> {
+        if let TokenValue::List(list) = object {
+            let position = self.list_position(list, index, bracket)?;
+            self.heap.list_mut(list)[position] = value.clone();
+            return Ok(value);
+        }
+
+        let arguments = [index, value.clone()];
+        let result = self.rooted(&object, |interpreter| {
+            interpreter.call_special(&object, "__setindex__", &arguments, Some(bracket))
+        })?;
+        match result {
+            Some(_) => Ok(value),
+            None => Err(InterpreterError {
+                message: InterpreterErrorMessage::UnexpectedValue {
+                    is: Box::new(object),
+                    expect: "List or Instance with __setindex__".to_string(),
+                },
+                token: Some(bracket.to_owned()),
+            }
+            .into()),
+        }
+    }
+
+    /// Position of the element `index` points at in `list`, which has to be a whole number
+    /// inside the list.
+    fn list_position(&self, list: Handle, index: TokenValue, bracket: &Token) -> Result<usize> {
+        let length = self.heap.list(list).len();
+        let position = self.as_number(index, bracket)?;
+        if position.fract() != 0.0 || position < 0.0 || position >= length as f64 {
+            return Err(InterpreterError {
+                message: InterpreterErrorMessage::IndexOutOfRange {
+                    index: position,
+                    length,
+                },
+                token: Some(bracket.to_owned()),
+            }
+            .into());
+        }
+
+        Ok(position as usize)
+    }
+
+    /// Reads `target`, applies `operator` to it and the value of `value` and stores the
+    /// result, returning both the value before and after. Values are left in `temps`.
+    fn update(
+        &mut self,
+        target: &mut Expression,
+        operator: &Token,
+        value: &mut Expression,
+    ) -> Result<(TokenValue, TokenValue)> {
+        match target {
+            Expression::Variable { name } => {
+                let old = self.visit_variable(name)?;
+                let new = self.combine(old.clone(), operator, value)?;
+                self.environment.assign(name, new.clone())?;
+                Ok((old, new))
+            }
+            Expression::Get { object, name } => {
+                let object = self.evaluate(object)?;
+                self.temps.extend(value_references(&object));
+                let old = self.get_member(object.clone(), name)?;
+                let new = self.combine(old.clone(), operator, value)?;
+                let new = self.set_member(object, name, new)?;
+                Ok((old, new))
+            }
+            Expression::Index {
+                object,
+                bracket,
+                index,
+            } => {
+                let object = self.evaluate(object)?;
+                self.temps.extend(value_references(&object));
+                let index = self.evaluate(index)?;
+                self.temps.extend(value_references(&index));
+                let old = self.get_index(object.clone(), bracket, index.clone())?;
+                let new = self.combine(old.clone(), operator, value)?;
+                let new = self.set_index(object, bracket, index, new)?;
+                Ok((old, new))
+            }
+            _ => Err(InterpreterError {
+                message: InterpreterErrorMessage::Unreachable,
+                token: Some(operator.to_owned()),
+            }
+            .into()),
+        }
+    }
+
+    /// `old operator value`, with `old` and the result left in `temps`.
+    fn combine(
+        &mut self,
+        old: TokenValue,
+        operator: &Token,
+        value: &mut Expression,
+    ) -> Result<TokenValue> {
+        self.temps.extend(value_references(&old));
+        let value = self.evaluate(value)?;
+        self.temps.extend(value_references(&value));
+        let new = self.operate(old, operator, value)?;
+        self.temps.extend(value_references(&new));
+        Ok(new)
+    }
+
+    /// Evaluates the arguments, rooting each of them, and calls `callee` with them.
+    fn call(
+        &mut self,
+        callee: TokenValue,
+        paren: &Token,
+        arguments: &mut [Expression],
+        named: &mut [NamedArgument],
+    ) -> Result<TokenValue> {
+        let mut val_arguments = Vec::new();
+        for argument in arguments {
+            let value = self.evaluate(argument)?;
+            self.temps.extend(value_references(&value));
+            val_arguments.push(value);
+        }
+
+        if !named.is_empty() {
+            let mut val_named = Vec::new();
+            for argument in named {
+                let value = self.evaluate(&mut argument.value)?;
+                self.temps.extend(value_references(&value));
+                val_named.push((argument.name.to_owned(), value));
+            }
+
+            let data = match &callee {
+                TokenValue::Function(function) => Some(function.data.to_owned()),
+                TokenValue::Class(class) => {
+                    let name = &self.heap.class(*class).name;
+                    self.heap
+                        .find_method(*class, self.initializer_name(name))
+                        .map(|initializer| initializer.data)
+                }
+                _ => None,
+            };
+            // Classes without an initializer take no arguments, anything else fails below
+            // for not being callable.
+            if let Some(data) = data {
+                val_arguments = Self::arrange(&data, val_arguments, val_named, paren)?;
+            } else if let TokenValue::Class(_) = callee {
+                let (name, _) = val_named.swap_remove(0);
+                return Err(InterpreterError {
+                    message: InterpreterErrorMessage::UnknownArgument {
+                        name: name.lexeme.to_owned(),
+                    },
+                    token: Some(name),
+                }
+                .into());
+            }
+        }
+
+        match callee {
+            TokenValue::Function(mut func) => {
+                self.call_function(&mut func, &val_arguments, Some(paren))
+            }
+            TokenValue::Class(class) => {
+                self.allocate(mem::size_of::<Instance>(), Some(paren))?;
+                self.instantiate(class, &val_arguments, Some(paren))
+            }
+            _ => Err(InterpreterError {
+                message: InterpreterErrorMessage::IsNotCallable,
+                token: Some(paren.to_owned()),
+            }
+            .into()),
+        }
+    }
+
+    fn native(name: &str, params: &[&str], call: NativeFn) -> TokenValue {
+        TokenValue::Function(Function {
+            data: FunctionData {
+                name: name.to_string(),
+                line: None,
+                body: None,
+                params: params.iter().map(|param| param.to_string()).collect(),
+                defaults: Vec::new(),
+                rest: false,
+                this: None,
+                class: None,
+                initializer: false,
+                closure: None,
+            },
+            call,
+        })
+    }
+
+    /// Creates an instance of `class` and runs its initializer with `arguments`.
+    fn instantiate(
+        &mut self,
+        class: Handle,
+        arguments: &[TokenValue],
+        call_site: Option<&Token>,
+    ) -> Result<TokenValue> {
+        let name = self.heap.class(class).name.to_owned();
+        let missing = self.heap.missing_methods(class);
+        if !missing.is_empty() {
+            return Err(InterpreterError {
+                message: InterpreterErrorMessage::AbstractClass {
+                    class: name,
+                    missing,
+                },
+                token: call_site.cloned(),
+            }
+            .into());
+        }
+
+        let initializer = self.heap.find_method(class, self.initializer_name(&name));
+
+        let arity = initializer
+            .as_ref()
+            .map_or((0, Some(0)), |initializer| initializer.data.arity());
+        Self::check_arity(arity, arguments.len(), call_site)?;
+
+        let instance = self.alloc(Object::Instance(Instance::new(class)));
+        let value = TokenValue::Instance(instance);
+        self.rooted(&value, |interpreter| {
+            interpreter.initialize_fields(instance, class)?;
+            if let Some(mut initializer) = initializer {
+                initializer.data.this = Some(instance);
+                interpreter.call_function(&mut initializer, arguments, call_site)?;
+            }
+            Ok(())
+        })?;
+
+        Ok(value)
+    }
+
+    /// Sets the fields declared by `class` on `instance`, those of its superclasses first.
+    /// Initializers run in declaration order and can use `this`.
+    fn initialize_fields(&mut self, instance: Handle, class: Handle) -> Result<()> {
+        if let Some(superclass) = self.heap.class(class).superclass {
+            self.initialize_fields(instance, superclass)?;
+        }
+
+        let fields = self.heap.class(class).fields().to_vec();
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        // Initializers see the scopes the class was declared in, not those of the caller.
+        let captured = self
+            .heap
+            .class(class)
+            .closure
+            .map_or_else(Vec::new, |closure| self.heap.closure(closure).to_vec());
+        let start = self.environment.environments().len();
+        for scope in &captured {
+            self.environment.push_environment(scope.clone());
+        }
+        self.environment.create_environment();
+        self.environment
+            .define("this".to_string(), TokenValue::Instance(instance))?;
+        let outer = self.environment.isolate(start);
+        let mut result = Ok(());
+        for field in fields {
+            let value = match field.initializer {
+                Some(mut initializer) => self.evaluate(&mut initializer),
+                None => Ok(TokenValue::Nil),
+            };
+            result = value.and_then(|value| {
+                self.allocate(field.name.len() + Usage::shallow(&value), None)?;
+                self.heap
+                    .instance_mut(instance)
+                    .set_field(class, field.name, value);
+                Ok(())
+            });
+            if result.is_err() {
+                break;
+            }
+        }
+        self.environment.restrict(outer);
+        self.environment.delete_environment()?;
+        for _ in &captured {
+            self.environment.delete_environment()?;
+        }
+
+        result
+    }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Interpreter {
-    environment: EnvironmentHandler,
-}
+    /// Field of `instance` named `name`, or a method of `class` bound to it.
+    fn get_property(&self, instance: Handle, class: Handle, name: &Token) -> Option<TokenValue> {
+        let object = self.heap.instance(instance);
+        if let Some(value) = object.field(class, &name.lexeme) {
+            return Some(value);
+        }
 
-impl Default for Interpreter {
-    fn default() -> Self {
-        let mut environment: EnvironmentHandler = Default::default();
-        environment
-            .define(
-                "print".to_string(),
-                TokenValue::Function(Function {
-                    data: FunctionData {
-                        body: None,
-                        params: vec!["text".to_string()],
-                        this: None,
-                    },
-                    call: |_, _, args| {
-                        println!("{}", args[0]);
-                        Ok(TokenValue::Nil)
-                    },
-                }),
-            )
-            .unwrap();
+        if self.legacy_init && name.lexeme == self.heap.class(object.class).name {
+            return Some(TokenValue::Instance(instance));
+        }
 
-        Self { environment }
+        self.heap
+            .find_method(class, &name.lexeme)
+            .map(|mut method| {
+                method.data.this = Some(instance);
+                TokenValue::Function(method)
+            })
     }
-}
 
-impl Interpreter {
-    pub fn interpret(&mut self, statements: &mut Vec<Statement>) -> Result<Option<TokenValue>> {
-        let mut last_value = None;
+    /// Class to look the member `name` of an object of `class` up in. Private members are
+    /// only reachable from methods of the class declaring them, and are looked up there.
+    fn member_class(&self, class: Handle, name: &Token) -> Result<Handle> {
+        if !name.lexeme.starts_with('#') {
+            return Ok(class);
+        }
 
-        for statement in statements {
-            last_value = statement.accept(self)?;
+        match self.frames.last().and_then(|frame| frame.class) {
+            Some(current)
+                if self.heap.inherits(class, current)
+                    && self.heap.class(current).declares(&name.lexeme) =>
+            {
+                Ok(current)
+            }
+            _ => Err(InterpreterError {
+                message: InterpreterErrorMessage::PrivateMember {
+                    name: name.lexeme.to_owned(),
+                },
+                token: Some(name.to_owned()),
+            }
+            .into()),
         }
+    }
 
-        Ok(last_value)
+    pub fn attach(&mut self, hook: Box<dyn Hook>) {
+        self.hook = Some(hook);
     }
 
-    fn evaluate(&mut self, expr: &mut Expression) -> Result<TokenValue> {
-        expr.accept::<Result<TokenValue>>(self)
+    pub fn detach(&mut self) -> Option<Box<dyn Hook>> {
+        self.hook.take()
+    }
+
+    /// Hands the hook out while it runs so it can drive the interpreter, e.g. to evaluate
+    /// a watch expression, without being notified about its own work.
+    fn notify(
+        &mut self,
+        notification: impl FnOnce(&mut dyn Hook, &mut Self) -> Result<()>,
+    ) -> Result<()> {
+        let Some(mut hook) = self.hook.take() else {
+            return Ok(());
+        };
+
+        let result = notification(hook.as_mut(), self);
+        if self.hook.is_none() {
+            self.hook = Some(hook);
+        }
+
+        result
     }
 
     fn as_number(&self, value: TokenValue, token: &Token) -> Result<f64> {
         value.clone().try_into().map_err(|_| {
             InterpreterError {
                 message: InterpreterErrorMessage::UnexpectedValue {
-                    is: value,
+                    is: Box::new(value),
                     expect: "Number".to_string(),
                 },
                 token: Some(token.clone()),
@@ -80,10 +1428,50 @@ impl Interpreter {
         })
     }
 
+    /// Scopes the current call created, which a function created now closes over. Functions
+    /// created at the top level don't capture anything, they look globals up when they run.
+    fn capture(&mut self, token: Option<&Token>) -> Result<Option<Handle>> {
+        let start = self
+            .frames
+            .last()
+            .map_or(1, |frame| frame.environment)
+            .max(1);
+        let scopes = self.environment.environments()[start..].to_vec();
+        if scopes.is_empty() {
+            return Ok(None);
+        }
+
+        let object = Object::Closure(scopes);
+        self.allocate(object.size(), token)?;
+
+        Ok(Some(self.alloc(object)))
+    }
+
+    /// Creates a function closing over `closure`, which stays alive while the defaults of
+    /// the parameters are evaluated. A function created in a method may use the private
+    /// members of its class, like the method itself.
+    fn create_closure(
+        &mut self,
+        closure: Option<Handle>,
+        name: &Token,
+        params: &[Parameter],
+        body: &mut Statement,
+    ) -> Result<Function> {
+        let base = self.temps.len();
+        self.temps.extend(closure);
+        let function = self.create_function(name, params, body);
+        self.temps.truncate(base);
+
+        let mut function = function?;
+        function.data.closure = closure;
+        function.data.class = self.frames.last().and_then(|frame| frame.class);
+        Ok(function)
+    }
+
     fn create_function(
         &mut self,
         name: &Token,
-        params: &[Token],
+        params: &[Parameter],
         body: &mut Statement,
     ) -> Result<Function> {
         let body = match body {
@@ -97,34 +1485,61 @@ impl Interpreter {
             }
         };
 
+        let base = self.temps.len();
+        let mut defaults = Vec::new();
+        for param in params {
+            if let Some(default) = &param.default {
+                let value = self.evaluate(&mut default.to_owned());
+                let Ok(value) = value else {
+                    self.temps.truncate(base);
+                    return value.map(|_| unreachable!());
+                };
+                self.temps.extend(value_references(&value));
+                defaults.push(value);
+            }
+        }
+        self.temps.truncate(base);
+
         Ok(Function {
             data: FunctionData {
-                body: Some(body.to_owned()),
-                params: params.iter().map(|param| param.lexeme.to_owned()).collect(),
+                name: name.lexeme.to_owned(),
+                line: Some(name.position.row),
+                body: Some(Arc::new(body.to_owned())),
+                params: params
+                    .iter()
+                    .map(|param| param.name.lexeme.to_owned())
+                    .collect(),
+                defaults,
+                rest: params.last().is_some_and(|param| param.rest),
                 this: None,
+                class: None,
+                initializer: false,
+                closure: None,
             },
             call: |interpreter, data, args| {
-                if data.params.len() != args.len() {
-                    return Err(InterpreterError {
-                        message: InterpreterErrorMessage::ArgumentMismatch {
-                            has: args.len(),
-                            expect: data.params.len(),
-                        },
-                        token: None,
-                    }
-                    .into());
+                let captured = data.closure.map_or_else(Vec::new, |closure| {
+                    interpreter.heap.closure(closure).to_vec()
+                });
+                // The body resolves names in the scopes it captured and the globals, never in
+                // the locals of whoever called it.
+                let start = interpreter.environment.environments().len();
+                for scope in &captured {
+                    interpreter.environment.push_environment(scope.clone());
                 }
-
                 interpreter.environment.create_environment();
 
-                if let Some(this) = data.this.clone() {
+                if let Some(this) = data.this {
                     interpreter
                         .environment
-                        .define("this".to_string(), TokenValue::Instance(this.clone()))?;
+                        .define("this".to_string(), TokenValue::Instance(this))?;
 
+                    let superclass = data
+                        .class
+                        .and_then(|class| interpreter.heap.class(class).superclass)
+                        .map_or(TokenValue::Nil, TokenValue::Class);
                     interpreter
                         .environment
-                        .define("super".to_string(), TokenValue::Instance(this))?;
+                        .define("super".to_string(), superclass)?;
                 }
 
                 for (index, param) in args.iter().enumerate() {
@@ -133,8 +1548,8 @@ impl Interpreter {
                         .define(data.params.get(index).unwrap().to_owned(), param.to_owned())?;
                 }
 
-                let mut body = match data.body.to_owned() {
-                    Some(body) => body,
+                let mut body = match &data.body {
+                    Some(body) => body.to_vec(),
                     None => {
                         return Err(InterpreterError {
                             message: InterpreterErrorMessage::MissingBlock,
@@ -143,11 +1558,18 @@ impl Interpreter {
                         .into());
                     }
                 };
-                let val = interpreter
-                    .visit_block(&mut body)?
-                    .unwrap_or(TokenValue::Nil);
+                let outer = interpreter.environment.isolate(start);
+                let result = interpreter.visit_block(&mut body);
+                interpreter.environment.restrict(outer);
                 interpreter.environment.delete_environment()?;
-                Ok(val)
+                for _ in &captured {
+                    interpreter.environment.delete_environment()?;
+                }
+                let val = result?.unwrap_or(TokenValue::Nil);
+                match (data.initializer, data.this) {
+                    (true, Some(this)) => Ok(TokenValue::Instance(this)),
+                    _ => Ok(val),
+                }
             },
         })
     }
@@ -167,68 +1589,46 @@ impl ExpressionVisitor<Result<TokenValue>> for Interpreter {
         right: &mut Expression,
     ) -> Result<TokenValue> {
         let left_val = self.evaluate(left)?;
-        let right_val = self.evaluate(right)?;
+        let right_val = self.rooted(&left_val, |interpreter| interpreter.evaluate(right))?;
 
-        match operator.kind {
-            TokenType::Plus => {
-                if let (TokenValue::String(_), _) | (_, TokenValue::String(_)) =
-                    (&left_val, &right_val)
-                {
-                    return Ok(TokenValue::String(format!("{}{}", left_val, right_val)));
-                }
-            }
-            TokenType::EqualEqual => {
-                return Ok(TokenValue::Bool(left_val == right_val));
-            }
-            TokenType::BangEqual => {
-                return Ok(TokenValue::Bool(left_val != right_val));
-            }
-            _ => {}
-        }
-
-        let left_num = self.as_number(left_val, operator)?;
-        let right_num = self.as_number(right_val, operator)?;
+        self.operate(left_val, operator, right_val)
+    }
 
-        match operator.kind {
-            TokenType::Plus => {
-                return Ok(TokenValue::Number(left_num + right_num));
-            }
-            TokenType::Minus => {
-                return Ok(TokenValue::Number(left_num - right_num));
-            }
-            TokenType::Slash => {
-                if right_num == 0.0 {
-                    return Err(InterpreterError {
-                        message: InterpreterErrorMessage::DivisionByZero,
-                        token: Some(operator.to_owned()),
-                    }
-                    .into());
+    fn visit_compound(
+        &mut self,
+        target: &mut Expression,
+        operator: &Token,
+        value: &mut Expression,
+        postfix: bool,
+    ) -> Result<TokenValue> {
+        let kind = match operator.kind {
+            TokenType::PlusEqual | TokenType::PlusPlus => TokenType::Plus,
+            TokenType::MinusEqual | TokenType::MinusMinus => TokenType::Minus,
+            TokenType::StarEqual => TokenType::Star,
+            TokenType::SlashEqual => TokenType::Slash,
+            TokenType::PercentEqual => TokenType::Percent,
+            _ => {
+                return Err(InterpreterError {
+                    message: InterpreterErrorMessage::Unreachable,
+                    token: Some(operator.to_owned()),
                 }
-                return Ok(TokenValue::Number(left_num / right_num));
-            }
-            TokenType::Star => {
-                return Ok(TokenValue::Number(left_num * right_num));
-            }
-            TokenType::Greater => {
-                return Ok(TokenValue::Bool(left_num > right_num));
-            }
-            TokenType::GreaterEqual => {
-                return Ok(TokenValue::Bool(left_num >= right_num));
-            }
-            TokenType::Less => {
-                return Ok(TokenValue::Bool(left_num < right_num));
-            }
-            TokenType::LessEqual => {
-                return Ok(TokenValue::Bool(left_num <= right_num));
+                .into());
             }
-            _ => {}
-        }
+        };
+        let binary = Token {
+            lexeme: kind.to_string(),
+            kind,
+            ..operator.to_owned()
+        };
 
-        Err(InterpreterError {
-            message: InterpreterErrorMessage::Unreachable,
-            token: Some(operator.to_owned()),
-        }
-        .into())
+        // The object and index of the target are evaluated once, and everything evaluated
+        // stays rooted until the update is stored.
+        let base = self.temps.len();
+        let result = self.update(target, &binary, value);
+        self.temps.truncate(base);
+
+        let (old, new) = result?;
+        Ok(if postfix { old } else { new })
     }
 
     fn visit_call(
@@ -236,55 +1636,67 @@ impl ExpressionVisitor<Result<TokenValue>> for Interpreter {
         callee: &mut Expression,
         paren: &Token,
         arguments: &mut [Expression],
+        named: &mut [NamedArgument],
     ) -> Result<TokenValue> {
         let callee = self.evaluate(callee)?;
 
-        let mut val_arguments = Vec::new();
-        for argument in arguments {
-            val_arguments.push(self.evaluate(argument)?);
-        }
+        // Arguments stay rooted until the callee defined them in its environment.
+        let base = self.temps.len();
+        self.temps.extend(value_references(&callee));
+        let result = self.call(callee, paren, arguments, named);
+        self.temps.truncate(base);
 
-        match callee {
-            TokenValue::Function(mut func) => {
-                Ok((func.call)(self, &mut func.data, &val_arguments)?)
-            }
-            TokenValue::Class(class) => Ok(TokenValue::Instance(Instance::new(
-                class.clone(),
-                self,
-                val_arguments,
-            )?)),
-            _ => Err(InterpreterError {
-                message: InterpreterErrorMessage::IsNotCallable,
-                token: Some(paren.to_owned()),
-            }
-            .into()),
-        }
+        result
     }
 
     fn visit_get(&mut self, object: &mut Expression, name: &Token) -> Result<TokenValue> {
         let object = self.evaluate(object)?;
+        self.get_member(object, name)
+    }
 
-        match object {
-            TokenValue::Instance(instance) => Ok(instance
-                .read()
-                .unwrap()
-                .get(name)
-                .unwrap_or(TokenValue::Nil)),
-            _ => Err(InterpreterError {
-                message: InterpreterErrorMessage::UnexpectedValue {
-                    is: object,
-                    expect: "Instance".to_string(),
-                },
-                token: Some(name.to_owned()),
-            }
-            .into()),
-        }
+    fn visit_index(
+        &mut self,
+        object: &mut Expression,
+        bracket: &Token,
+        index: &mut Expression,
+    ) -> Result<TokenValue> {
+        let object = self.evaluate(object)?;
+        let index = self.rooted(&object, |interpreter| interpreter.evaluate(index))?;
+        self.get_index(object, bracket, index)
+    }
+
+    fn visit_set_index(
+        &mut self,
+        object: &mut Expression,
+        bracket: &Token,
+        index: &mut Expression,
+        value: &mut Expression,
+    ) -> Result<TokenValue> {
+        let object = self.evaluate(object)?;
+        let index = self.rooted(&object, |interpreter| interpreter.evaluate(index))?;
+        let value = self.rooted(&object, |interpreter| {
+            interpreter.rooted(&index, |interpreter| interpreter.evaluate(value))
+        })?;
+        self.set_index(object, bracket, index, value)
     }
 
     fn visit_grouping(&mut self, expression: &mut Expression) -> Result<TokenValue> {
         self.evaluate(expression)
     }
 
+    fn visit_lambda(
+        &mut self,
+        keyword: &Token,
+        params: &[Parameter],
+        body: &mut Statement,
+    ) -> Result<TokenValue> {
+        let closure = self.capture(Some(keyword))?;
+        let mut function = self.create_closure(closure, keyword, params, body)?;
+        function.data.name = "lambda".to_string();
+
+        Ok(TokenValue::Function(function))
+    }
+
     fn visit_literal(&mut self, value: &TokenValue) -> Result<TokenValue> {
         Ok(value.to_owned())
     }
@@ -297,17 +1709,9 @@ impl ExpressionVisitor<Result<TokenValue>> for Interpreter {
     ) -> Result<TokenValue> {
         let left = self.evaluate(left)?;
 
-        match operator.kind {
-            TokenType::Or => {
-                if bool::try_from(left.clone())? {
-                    return Ok(left);
-                }
-            }
-            TokenType::And => {
-                if !bool::try_from(left.clone())? {
-                    return Ok(left);
-                }
-            }
+        let taken = match operator.kind {
+            TokenType::Or => !bool::try_from(left.clone())?,
+            TokenType::And => bool::try_from(left.clone())?,
             _ => {
                 return Err(InterpreterError {
                     token: Some(operator.to_owned()),
@@ -315,9 +1719,17 @@ impl ExpressionVisitor<Result<TokenValue>> for Interpreter {
                 }
                 .into());
             }
+        };
+        if self.hook.is_some() {
+            self.notify(|hook, interpreter| {
+                hook.on_branch(interpreter, &operator.position, taken)
+            })?;
         }
 
-        self.evaluate(right)
+        match taken {
+            true => self.evaluate(right),
+            false => Ok(left),
+        }
     }
 
     fn visit_set(
@@ -327,39 +1739,15 @@ impl ExpressionVisitor<Result<TokenValue>> for Interpreter {
         value: &mut Expression,
     ) -> Result<TokenValue> {
         let object = self.evaluate(object)?;
-        let value = self.evaluate(value)?;
-
-        match object {
-            TokenValue::Instance(instance) => {
-                instance
-                    .write()
-                    .unwrap()
-                    .fields
-                    .define(name.lexeme.to_owned(), value.clone());
-                Ok(value)
-            }
-            _ => Err(InterpreterError {
-                message: InterpreterErrorMessage::UnexpectedValue {
-                    is: object,
-                    expect: "Instace".to_string(),
-                },
-                token: Some(name.to_owned()),
-            }
-            .into()),
-        }
+        let value = self.rooted(&object, |interpreter| interpreter.evaluate(value))?;
+        self.set_member(object, name, value)
     }
 
     fn visit_super(&mut self, keyword: &Token, method: &Token) -> Result<TokenValue> {
-        if let Some(instance) = self.environment.get(keyword)
-            && let TokenValue::Instance(instance) = instance
+        if let Some(TokenValue::Class(superclass)) = self.environment.get(keyword)
+            && let Some(TokenValue::Instance(instance)) = self.environment.lookup("this")
         {
-            return match instance
-                .clone()
-                .read()
-                .unwrap()
-                .class
-                .get_from_super(method.lexeme.to_owned())
-            {
+            return match self.heap.find_method(superclass, &method.lexeme) {
                 Some(mut method) => {
                     method.data.this = Some(instance);
                     return Ok(TokenValue::Function(method));
@@ -399,6 +1787,12 @@ impl ExpressionVisitor<Result<TokenValue>> for Interpreter {
                 return Ok(TokenValue::Bool(!val));
             }
             TokenType::Minus => {
+                if let Some(value) =
+                    self.call_special(&right_val, "__neg__", &[], Some(operator))?
+                {
+                    return Ok(value);
+                }
+
                 let num = self.as_number(right_val, operator)?;
                 return Ok(TokenValue::Number(-num));
             }
@@ -430,7 +1824,7 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
     fn visit_block(&mut self, statements: &mut [Statement]) -> Result<Option<TokenValue>> {
         self.environment.create_environment();
         for stmt in statements {
-            match stmt.accept(self) {
+            match self.execute(stmt) {
                 Ok(_) => {}
                 Err(err) => {
                     self.environment.delete_environment()?;
@@ -451,7 +1845,8 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
         &mut self,
         name: &Token,
         superclass: &mut Option<Box<Expression>>,
-        methods: &mut [Statement],
+        traits: &mut [Expression],
+        body: &mut ClassBody,
     ) -> Result<Option<TokenValue>> {
         let superclass = match superclass {
             Some(val) => {
@@ -461,7 +1856,7 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
                     _ => {
                         return Err(InterpreterError {
                             message: InterpreterErrorMessage::UnexpectedValue {
-                                is: val,
+                                is: Box::new(val),
                                 expect: "Class".to_string(),
                             },
                             token: Some(name.to_owned()),
@@ -473,21 +1868,16 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
             None => None,
         };
 
-        self.environment
-            .define(name.lexeme.clone(), TokenValue::Nil)?;
-
-        let mut methods_map = HashMap::new();
-        for method in methods {
-            match method {
-                Statement::Function { name, params, body } => {
-                    methods_map.insert(
-                        name.lexeme.clone(),
-                        self.create_function(name, params, body)?,
-                    );
-                }
-                _ => {
+        let mut definitions = Vec::new();
+        for definition in traits {
+            match self.evaluate(definition)? {
+                TokenValue::Trait(definition) => definitions.push(definition),
+                value => {
                     return Err(InterpreterError {
-                        message: InterpreterErrorMessage::IsNotCallable,
+                        message: InterpreterErrorMessage::UnexpectedValue {
+                            is: Box::new(value),
+                            expect: "Trait".to_string(),
+                        },
                         token: Some(name.to_owned()),
                     }
                     .into());
@@ -495,13 +1885,66 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
             }
         }
 
-        let class = TokenValue::Class(Arc::new(Class::new(
-            name.lexeme.clone(),
-            superclass,
-            methods_map,
-        )));
+        // The class is allocated first so its methods can know where they were declared.
+        let mut declaration = Class::new(name.lexeme.clone(), superclass);
+        declaration.traits = definitions.clone();
+        let handle = self.alloc(Object::Class(Box::new(declaration)));
+        let class = TokenValue::Class(handle);
+        self.environment
+            .define(name.lexeme.clone(), class.clone())?;
+        self.heap.class_mut(handle).closure = self.capture(Some(name))?;
+
+        self.compose(name, handle, &definitions, &body.methods)?;
+        self.heap.class_mut(handle).abstracts = body
+            .abstracts
+            .iter()
+            .map(|method| method.name.lexeme.to_owned())
+            .collect();
+
+        let initializer = self.initializer_name(&name.lexeme).to_owned();
+        for method in &mut body.methods {
+            let mut method = self.method(handle, method)?;
+            method.data.initializer = method.data.name == initializer;
+            self.heap.class_mut(handle).define_method(method);
+        }
+        for getter in &mut body.getters {
+            let getter = self.method(handle, getter)?;
+            self.heap.class_mut(handle).define_getter(getter);
+        }
+        for setter in &mut body.setters {
+            let setter = self.method(handle, setter)?;
+            self.heap.class_mut(handle).define_setter(setter);
+        }
+        for field in &body.fields {
+            if let Statement::Var { name, initializer } = field {
+                self.heap.class_mut(handle).declare_field(Field {
+                    name: name.lexeme.to_owned(),
+                    initializer: initializer.as_deref().cloned(),
+                });
+            }
+        }
+
+        // Static fields are initialized in order once the class exists, so they can use it.
+        for member in &mut body.statics {
+            let name = match member {
+                Statement::Function { name, .. } | Statement::Var { name, .. } => name.to_owned(),
+                _ => continue,
+            };
+            let value = match member {
+                Statement::Var {
+                    initializer: Some(initializer),
+                    ..
+                } => self.evaluate(initializer)?,
+                Statement::Var { .. } => TokenValue::Nil,
+                function => TokenValue::Function(self.method(handle, function)?),
+            };
+            self.allocate(name.lexeme.len() + Usage::shallow(&value), Some(&name))?;
+            self.heap
+                .class_mut(handle)
+                .statics
+                .define(name.lexeme, value);
+        }
 
-        self.environment.assign(name, class.clone())?;
         Ok(Some(class))
     }
 
@@ -512,24 +1955,32 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
     fn visit_function(
         &mut self,
         name: &Token,
-        params: &[Token],
+        params: &[Parameter],
         body: &mut Statement,
     ) -> Result<Option<TokenValue>> {
-        let function = TokenValue::Function(self.create_function(name, params, body)?);
-        self.environment.define(name.lexeme.to_owned(), function)?;
+        let closure = self.capture(Some(name))?;
+        let function = self.create_closure(closure, name, params, body)?;
+        self.environment
+            .define(name.lexeme.to_owned(), TokenValue::Function(function))?;
         Ok(None)
     }
 
     fn visit_if(
         &mut self,
+        keyword: &Token,
         condition: &mut Expression,
         then_branch: &mut Statement,
         else_branch: &mut Option<Box<Statement>>,
     ) -> Result<Option<TokenValue>> {
-        if bool::try_from(self.evaluate(condition)?)? {
-            then_branch.accept(self)?;
+        let taken = bool::try_from(self.evaluate(condition)?)?;
+        if self.hook.is_some() {
+            self.notify(|hook, interpreter| hook.on_branch(interpreter, &keyword.position, taken))?;
+        }
+
+        if taken {
+            self.execute(then_branch)?;
         } else if let Some(else_branch) = else_branch {
-            else_branch.accept(self)?;
+            self.execute(else_branch)?;
         }
 
         Ok(None)
@@ -544,6 +1995,27 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
         Err(ReturnValue(value).into())
     }
 
+    fn visit_trait(
+        &mut self,
+        name: &Token,
+        methods: &mut [Statement],
+    ) -> Result<Option<TokenValue>> {
+        let handle = self.alloc(Object::Trait(Trait::new(name.lexeme.clone())));
+        let definition = TokenValue::Trait(handle);
+        self.environment
+            .define(name.lexeme.clone(), definition.clone())?;
+
+        let closure = self.capture(Some(name))?;
+        for method in methods {
+            if let Statement::Function { name, params, body } = method {
+                let method = self.create_closure(closure, name, params, body)?;
+                self.heap.get_trait_mut(handle).define_method(method);
+            }
+        }
+
+        Ok(Some(definition))
+    }
+
     fn visit_var(
         &mut self,
         name: &Token,
@@ -565,9 +2037,195 @@ impl StatementVisitor<Result<Option<TokenValue>>> for Interpreter {
         body: &mut Statement,
     ) -> Result<Option<TokenValue>> {
         while bool::try_from(self.evaluate(condition)?)? {
-            body.accept(self)?;
+            self.execute(body)?;
         }
 
         Ok(None)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interpreter::testing::{capture, fail, run};
+
+    #[test]
+    fn compares_objects_by_identity() {
+        let output = run("class A { m() {} }
+class B { m() {} }
+var a = A();
+var other = A();
+fun f(x) {}
+fun g(x) {}
+print(a == a);
+print(a == other);
+print(A == A);
+print(A == B);
+print(f == f);
+print(f == g);
+print(a.m == a.m);
+print(a.m == other.m);
+print(print == print);");
+        assert_eq!(
+            output,
+            "true\nfalse\ntrue\nfalse\ntrue\nfalse\ntrue\nfalse\ntrue\n"
+        );
+    }
+
+    #[test]
+    fn dispatches_equality_to_equals() {
+        let output = run("class Point {
+  init(x, y) { this.x = x; this.y = y; }
+  equals(other) { return this.x == other.x and this.y == other.y; }
+}
+print(Point(1, 2) == Point(1, 2));
+print(Point(1, 2) != Point(1, 2));
+print(Point(1, 2) == Point(2, 1));");
+        assert_eq!(output, "true\nfalse\nfalse\n");
+    }
+
+    #[test]
+    fn dispatches_operators_to_special_methods() {
+        let output = run("class Vector {
+  init(x, y) { this.x = x; this.y = y; }
+  __add__(other) { return Vector(this.x + other.x, this.y + other.y); }
+  __sub__(other) { return Vector(this.x - other.x, this.y - other.y); }
+  __mul__(factor) { return Vector(this.x * factor, this.y * factor); }
+  __div__(factor) { return Vector(this.x / factor, this.y / factor); }
+  __neg__() { return Vector(-this.x, -this.y); }
+  __lt__(other) { return this.x * this.x + this.y * this.y < other.x * other.x + other.y * other.y; }
+  __index__(i) { if (i == 0) return this.x; return this.y; }
+}
+var a = Vector(1, 2);
+var b = Vector(3, 4);
+var c = (a + b) * 2 - a / 1;
+print(c[0]);
+print(c[1]);
+print((-a)[1]);
+print(a < b);
+print(a > b);
+print(a <= a);
+print(b >= a);
+class Tagged < Vector { toString() { return \"<\" + this.x + \">\"; } }
+print(Tagged(1, 2) + \"!\");
+print(\"?\" + Tagged(3, 4));");
+        assert_eq!(output, "7\n10\n-2\ntrue\nfalse\ntrue\ntrue\n<1>!\n?<3>\n");
+
+        assert!(fail("class A {}\nA() * 2;").starts_with("[2:5] Interpreter Error"));
+    }
+
+    #[test]
+    fn converts_values_to_strings() {
+        let output = run("class Point {
+  init(x, y) { this.x = x; this.y = y; }
+}
+class Money {
+  init(cents) { this.cents = cents; }
+  toString() { return \"$\" + this.cents / 100; }
+}
+fun add(a, b) { return a + b; }
+print(add);
+print(Point);
+print(print);
+print(Point(1, \"a\"));
+print(Money(250));
+print(\"total: \" + Money(150));
+print(Money(150) + \"!\");
+fun list(...items) { return items; }
+print(list(Money(250), Point(Money(1), nil)));
+");
+        assert_eq!(
+            output,
+            "<fn add/2>\n<class Point>\n<native fn print>\nPoint { x: 1, y: a }\n$2.5\ntotal: $1.5\n$1.5!\n\
+[$2.5, Point { x: $0.01, y: nil }]\n"
+        );
+    }
+
+    #[test]
+    fn initializes_instances_with_init() {
+        let output = run("class Point {
+  init(x, y) { this.x = x; this.y = y; return; }
+}
+class Point3 < Point {}
+var p = Point(1, 2);
+print(p.init(3, 4) == p);
+print(p.x);
+print(Point3(5, 6).y);");
+        assert_eq!(output, "true\n3\n6\n");
+
+        assert!(fail("class A { init(a) {} }\nA();").contains("Expected 1 arguments but got 0"));
+        assert!(fail("class A {}\nA(1);").contains("Expected 0 arguments but got 1"));
+
+        let tokens = crate::lexer::run("class A { init() { return 1; } }".to_string()).unwrap();
+        let (_, errors) = crate::parser::Parser::new(tokens).parse_with_errors();
+        assert_eq!(
+            errors[0].to_string(),
+            "[1:20] Parser Error: Can't return a value from an initializer\nreturn"
+        );
+
+        let mut interpreter = Interpreter::default();
+        interpreter.set_legacy_init(true);
+        let output = capture(
+            &mut interpreter,
+            "class A { A(x) { this.x = x; } }\nprint(A(1).x);",
+        );
+        assert_eq!(output.unwrap(), "1\n");
+    }
+
+    #[test]
+    fn dispatches_super_to_the_declaring_class() {
+        let output = run("class A {
+  init() { this.path = \"A\"; }
+  speak() { return \"A\"; }
+}
+class B < A {
+  init() { super.init(); this.path = this.path + \"B\"; }
+  speak() { return super.speak() + \"B\"; }
+}
+class C < B {
+  speak() { return super.speak() + \"C\"; }
+}
+class D < C {
+  init() { super.init(); this.path = this.path + \"D\"; }
+  speak() { return super.speak() + \"D\"; }
+}
+print(C().speak());
+print(D().speak());
+print(D().path);
+var speak = D().speak;
+print(speak());");
+        assert_eq!(output, "ABC\nABCD\nABD\nABCD\n");
+    }
+
+    #[test]
+    fn updates_targets_in_place() {
+        let output = run("print(-7 % 3);
+print(7 % -3);
+var i = 10;
+i += 2;
+i -= 1;
+i *= 2;
+i /= 11;
+i %= 3;
+print(i);
+print(i++);
+print(i);
+print(--i);
+class Counter { init() { this.count = 0; } }
+var counter = Counter();
+var reads = 0;
+fun current() { reads++; return counter; }
+current().count += 5;
+current().count++;
+print(counter.count);
+print(reads);
+var list = fields(counter);
+var lookups = 0;
+fun first() { lookups++; return 0; }
+list[first()] += \"!\";
+print(list);
+print(lookups);
+");
+        assert_eq!(output, "2\n-2\n2\n2\n3\n2\n6\n2\n[count!]\n1\n");
+    }
+}
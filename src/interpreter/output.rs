@@ -0,0 +1,23 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+/// Output sink whose clones share one buffer, so what the interpreter prints can be read
+/// back by whoever handed it over.
+#[derive(Debug, Clone, Default)]
+pub struct Captured(Rc<RefCell<Vec<u8>>>);
+
+impl Captured {
+    /// Empties the buffer, returning what was written since the last call.
+    pub fn take(&self) -> Vec<u8> {
+        self.0.take()
+    }
+}
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
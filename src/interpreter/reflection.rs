@@ -0,0 +1,244 @@
+use anyhow::Result;
+
+use crate::{
+    interpreter::{
+        Interpreter,
+        error::{InterpreterError, InterpreterErrorMessage},
+    },
+    memory::{heap::Handle, usage::Usage},
+    token::value::TokenValue,
+};
+
+impl Interpreter {
+    /// Natives that let scripts inspect values and access properties by name.
+    pub(super) fn reflection() -> Vec<(&'static str, TokenValue)> {
+        vec![
+            (
+                "type",
+                Self::native("type", &["value"], |_, _, args| {
+                    Ok(args[0].type_name().into())
+                }),
+            ),
+            (
+                "len",
+                Self::native("len", &["value"], |interpreter, _, args| match &args[0] {
+                    TokenValue::List(list) => Ok(interpreter.heap.list(*list).len().into()),
+                    TokenValue::String(text) => Ok(text.chars().count().into()),
+                    value => Err(Self::unexpected(value, "List or String")),
+                }),
+            ),
+            (
+                "instanceof",
+                Self::native(
+                    "instanceof",
+                    &["object", "class"],
+                    |interpreter, _, args| {
+                        let class = interpreter.expect_class(&args[1])?;
+                        Ok(match &args[0] {
+                            TokenValue::Instance(instance) => {
+                                let of = interpreter.heap.instance(*instance).class;
+                                interpreter.heap.inherits(of, class)
+                            }
+                            _ => false,
+                        }
+                        .into())
+                    },
+                ),
+            ),
+            (
+                "fields",
+                Self::native("fields", &["object"], |interpreter, _, args| {
+                    let instance = interpreter.expect_instance(&args[0])?;
+                    let mut names: Vec<String> = interpreter
+                        .heap
+                        .instance(instance)
+                        .fields
+                        .iter()
+                        .map(|(name, _)| name.to_owned())
+                        .collect();
+                    names.sort();
+                    interpreter.list(names.into_iter().map(TokenValue::String).collect())
+                }),
+            ),
+            (
+                "methods",
+                Self::native("methods", &["class"], |interpreter, _, args| {
+                    let class = interpreter.expect_class(&args[0])?;
+                    let mut names = interpreter.heap.method_names(class);
+                    names.retain(|name| !name.starts_with('#'));
+                    interpreter.list(names.into_iter().map(TokenValue::String).collect())
+                }),
+            ),
+            (
+                "superclass",
+                Self::native("superclass", &["class"], |interpreter, _, args| {
+                    let class = interpreter.expect_class(&args[0])?;
+                    Ok(interpreter
+                        .heap
+                        .class(class)
+                        .superclass
+                        .map_or(TokenValue::Nil, TokenValue::Class))
+                }),
+            ),
+            (
+                "name",
+                Self::native("name", &["value"], |interpreter, _, args| match &args[0] {
+                    TokenValue::Function(function) => Ok(function.data.name.as_str().into()),
+                    TokenValue::Class(class) => {
+                        Ok(interpreter.heap.class(*class).name.as_str().into())
+                    }
+                    TokenValue::Trait(definition) => {
+                        Ok(interpreter.heap.get_trait(*definition).name.as_str().into())
+                    }
+                    value => Err(Self::unexpected(value, "Function, Class or Trait")),
+                }),
+            ),
+            (
+                "arity",
+                Self::native("arity", &["function"], |_, _, args| match &args[0] {
+                    TokenValue::Function(function) => Ok(function.data.arity().0.into()),
+                    value => Err(Self::unexpected(value, "Function")),
+                }),
+            ),
+            (
+                "has_field",
+                Self::native("has_field", &["object", "name"], |interpreter, _, args| {
+                    let instance = interpreter.expect_instance(&args[0])?;
+                    let name = Self::expect_string(&args[1])?;
+                    if name.starts_with('#') {
+                        return Ok(false.into());
+                    }
+                    Ok(interpreter
+                        .heap
+                        .instance(instance)
+                        .fields
+                        .lookup(&name)
+                        .is_some()
+                        .into())
+                }),
+            ),
+            (
+                "get_field",
+                Self::native("get_field", &["object", "name"], |interpreter, _, args| {
+                    let instance = interpreter.expect_instance(&args[0])?;
+                    let name = Self::expect_string(&args[1])?;
+                    if name.starts_with('#') {
+                        return Ok(TokenValue::Nil);
+                    }
+                    Ok(interpreter
+                        .heap
+                        .instance(instance)
+                        .fields
+                        .lookup(&name)
+                        .unwrap_or(TokenValue::Nil))
+                }),
+            ),
+            (
+                "set_field",
+                Self::native(
+                    "set_field",
+                    &["object", "name", "value"],
+                    |interpreter, _, args| {
+                        let instance = interpreter.expect_instance(&args[0])?;
+                        let name = Self::field_name(&args[1])?;
+                        let value = args[2].to_owned();
+                        interpreter.allocate(name.len() + Usage::shallow(&value), None)?;
+                        interpreter
+                            .heap
+                            .instance_mut(instance)
+                            .fields
+                            .define(name, value.clone());
+                        Ok(value)
+                    },
+                ),
+            ),
+        ]
+    }
+
+    fn expect_instance(&self, value: &TokenValue) -> Result<Handle> {
+        match value {
+            TokenValue::Instance(instance) => Ok(*instance),
+            value => Err(Self::unexpected(value, "Instance")),
+        }
+    }
+
+    /// Class `value` is, or the class of `value` when it is an instance.
+    fn expect_class(&self, value: &TokenValue) -> Result<Handle> {
+        match value {
+            TokenValue::Class(class) => Ok(*class),
+            TokenValue::Instance(instance) => Ok(self.heap.instance(*instance).class),
+            value => Err(Self::unexpected(value, "Class")),
+        }
+    }
+
+    /// Name of a field set by reflection, which can't reach private fields.
+    fn field_name(value: &TokenValue) -> Result<String> {
+        match Self::expect_string(value)? {
+            name if name.starts_with('#') => Err(InterpreterError {
+                message: InterpreterErrorMessage::PrivateMember { name },
+                token: None,
+            }
+            .into()),
+            name => Ok(name),
+        }
+    }
+
+    /// Text of `value`, which names a field or method.
+    fn expect_string(value: &TokenValue) -> Result<String> {
+        match value {
+            TokenValue::String(name) => Ok(name.to_owned()),
+            value => Err(Self::unexpected(value, "String")),
+        }
+    }
+
+    fn unexpected(value: &TokenValue, expect: &str) -> anyhow::Error {
+        InterpreterError {
+            message: InterpreterErrorMessage::UnexpectedValue {
+                is: Box::new(value.to_owned()),
+                expect: expect.to_string(),
+            },
+            token: None,
+        }
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::testing::run;
+
+    #[test]
+    fn inspects_values_through_reflection() {
+        let output = run("class Shape { area() { return 0; } }
+class Square < Shape {
+  #secret = 1;
+  init(side) { this.side = side; }
+  area() { return this.side * this.side; }
+  get perimeter { return 4 * this.side; }
+}
+var square = Square(3);
+print(type(1) + type(\"a\") + type(nil) + type(square) + type(Square) + type(print));
+print(instanceof(square, Shape));
+print(instanceof(Shape(), Square));
+print(fields(square));
+print(methods(Square));
+print(superclass(Square));
+print(superclass(Shape));
+print(name(square.area) + name(Square));
+print(arity(print));
+print(has_field(square, \"side\"));
+set_field(square, \"color\", \"red\");
+print(get_field(square, \"color\"));
+print(get_field(square, \"missing\"));
+print(has_field(square, \"#secret\"));
+print(get_field(square, \"#secret\"));
+var names = fields(square);
+print(len(names));
+print(names[1]);
+");
+        assert_eq!(
+            output,
+            "numberstringnilinstanceclassfunction\ntrue\nfalse\n[side]\n[area, init]\n<class Shape>\nnil\nareaSquare\n1\ntrue\nred\nnil\nfalse\nnil\n2\nside\n"
+        );
+    }
+}
@@ -0,0 +1,26 @@
+//! Fixtures for tests that run scripts end to end.
+
+use anyhow::Result;
+
+use crate::interpreter::{Interpreter, output::Captured};
+
+/// Runs `source` in `interpreter`, returning what it printed or the error that stopped it.
+pub fn capture(interpreter: &mut Interpreter, source: &str) -> Result<String> {
+    let output = Captured::default();
+    interpreter.set_output(Box::new(output.clone()));
+    crate::run(interpreter, source.to_string())?;
+
+    Ok(String::from_utf8(output.take()).unwrap())
+}
+
+/// Runs `source` in a fresh interpreter and returns what it printed.
+pub fn run(source: &str) -> String {
+    capture(&mut Interpreter::default(), source).unwrap()
+}
+
+/// Runs `source` in a fresh interpreter and returns the error that stopped it.
+pub fn fail(source: &str) -> String {
+    capture(&mut Interpreter::default(), source)
+        .unwrap_err()
+        .to_string()
+}
@@ -4,6 +4,7 @@ use crate::token::kind::TokenType;
 
 pub fn create_keywords() -> HashMap<&'static str, TokenType> {
     let mut keywords = HashMap::new();
+    keywords.insert("abstract", TokenType::Abstract);
     keywords.insert("and", TokenType::And);
     keywords.insert("class", TokenType::Class);
     keywords.insert("else", TokenType::Else);
@@ -14,10 +15,13 @@ pub fn create_keywords() -> HashMap<&'static str, TokenType> {
     keywords.insert("nil", TokenType::Nil);
     keywords.insert("or", TokenType::Or);
     keywords.insert("return", TokenType::Return);
+    keywords.insert("static", TokenType::Static);
     keywords.insert("super", TokenType::Super);
     keywords.insert("this", TokenType::This);
+    keywords.insert("trait", TokenType::Trait);
     keywords.insert("true", TokenType::True);
     keywords.insert("var", TokenType::Var);
     keywords.insert("while", TokenType::While);
+    keywords.insert("with", TokenType::With);
     keywords
 }
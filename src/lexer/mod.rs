@@ -2,7 +2,7 @@ use crate::{lexer::scanner::Scanner, token::Token};
 
 mod emitter;
 mod error;
-mod keywords;
+pub mod keywords;
 mod reader;
 mod scanner;
 
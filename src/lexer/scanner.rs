@@ -46,12 +46,49 @@ impl Scanner {
             ')' => self.add_token(TokenType::RightParen, None),
             '{' => self.add_token(TokenType::LeftBrace, None),
             '}' => self.add_token(TokenType::RightBrace, None),
+            '[' => self.add_token(TokenType::LeftBracket, None),
+            ']' => self.add_token(TokenType::RightBracket, None),
+            ':' => self.add_token(TokenType::Colon, None),
             ',' => self.add_token(TokenType::Comma, None),
+            '.' if self.reader.peek() == '.' && self.reader.peek_next() == '.' => {
+                self.reader.advance()?;
+                self.reader.advance()?;
+                self.add_token(TokenType::Ellipsis, None);
+            }
             '.' => self.add_token(TokenType::Dot, None),
-            '-' => self.add_token(TokenType::Minus, None),
-            '+' => self.add_token(TokenType::Plus, None),
+            '-' => {
+                if self.reader.next_is('=') {
+                    self.add_token(TokenType::MinusEqual, None);
+                } else if self.reader.next_is('-') {
+                    self.add_token(TokenType::MinusMinus, None);
+                } else {
+                    self.add_token(TokenType::Minus, None);
+                }
+            }
+            '+' => {
+                if self.reader.next_is('=') {
+                    self.add_token(TokenType::PlusEqual, None);
+                } else if self.reader.next_is('+') {
+                    self.add_token(TokenType::PlusPlus, None);
+                } else {
+                    self.add_token(TokenType::Plus, None);
+                }
+            }
             ';' => self.add_token(TokenType::Semicolon, None),
-            '*' => self.add_token(TokenType::Star, None),
+            '*' => {
+                if self.reader.next_is('=') {
+                    self.add_token(TokenType::StarEqual, None);
+                } else {
+                    self.add_token(TokenType::Star, None);
+                }
+            }
+            '%' => {
+                if self.reader.next_is('=') {
+                    self.add_token(TokenType::PercentEqual, None);
+                } else {
+                    self.add_token(TokenType::Percent, None);
+                }
+            }
             '!' => {
                 if self.reader.next_is('=') {
                     self.add_token(TokenType::BangEqual, None);
@@ -62,6 +99,8 @@ impl Scanner {
             '=' => {
                 if self.reader.next_is('=') {
                     self.add_token(TokenType::EqualEqual, None);
+                } else if self.reader.next_is('>') {
+                    self.add_token(TokenType::Arrow, None);
                 } else {
                     self.add_token(TokenType::Equal, None);
                 }
@@ -96,6 +135,8 @@ impl Scanner {
                         self.reader.advance()?; // *
                         self.reader.advance()?; // /
                     }
+                } else if self.reader.next_is('=') {
+                    self.add_token(TokenType::SlashEqual, None);
                 } else {
                     self.add_token(TokenType::Slash, None);
                 }
@@ -103,6 +144,10 @@ impl Scanner {
             '"' => self.string()?,
             '0'..='9' => self.number()?,
             'a'..='z' | 'A'..='Z' | '_' => self.identifier()?,
+            // Private members are identifiers starting with `#`.
+            '#' if self.reader.peek().is_ascii_alphabetic() || self.reader.peek() == '_' => {
+                self.identifier()?
+            }
             '\r' | '\t' | ' ' => {}
             '\n' | '\0' => {
                 self.reader.next_row();
@@ -178,7 +223,7 @@ impl Scanner {
     }
 
     fn identifier(&mut self) -> Result<()> {
-        while self.reader.peek().is_ascii_alphanumeric() {
+        while self.reader.peek().is_ascii_alphanumeric() || self.reader.peek() == '_' {
             self.reader.advance()?;
         }
 
@@ -461,4 +506,21 @@ mod tests {
             error(LexerErrorMessage::UnexpectedCharacter, "π", pos(1, 2)).to_string()
         );
     }
+
+    #[test]
+    fn compound_operators() {
+        let res = scan("+= -= *= /= %= ++ -- %").unwrap();
+        let expected = vec![
+            token(TokenType::PlusEqual, None, "+=", pos(1, 1)),
+            token(TokenType::MinusEqual, None, "-=", pos(1, 4)),
+            token(TokenType::StarEqual, None, "*=", pos(1, 7)),
+            token(TokenType::SlashEqual, None, "/=", pos(1, 10)),
+            token(TokenType::PercentEqual, None, "%=", pos(1, 13)),
+            token(TokenType::PlusPlus, None, "++", pos(1, 16)),
+            token(TokenType::MinusMinus, None, "--", pos(1, 19)),
+            token(TokenType::Percent, None, "%", pos(1, 22)),
+            token(TokenType::EndOfFile, None, "", pos(1, 23)),
+        ];
+        assert_eq!(res, expected);
+    }
 }
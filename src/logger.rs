@@ -0,0 +1,107 @@
+use std::env;
+
+use log::{LevelFilter, Log, Metadata, Record, SetLoggerError};
+
+/// Backend for the `log` facade writing to stderr. The filter follows `RUST_LOG`: a comma
+/// separated list of `level` and `target=level` directives, where the most specific target
+/// prefix wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Logger {
+    default: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl Logger {
+    /// Installs the logger, using `fallback` as the filter when `RUST_LOG` is not set.
+    pub fn init(fallback: &str) -> Result<(), SetLoggerError> {
+        let spec = env::var("RUST_LOG").unwrap_or_else(|_| fallback.to_string());
+        let logger = Self::parse(&spec);
+
+        log::set_max_level(logger.max_level());
+        log::set_logger(Box::leak(Box::new(logger)))
+    }
+
+    pub fn parse(spec: &str) -> Self {
+        let mut logger = Self {
+            default: LevelFilter::Error,
+            directives: Vec::new(),
+        };
+
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    if let Ok(level) = level.trim().parse() {
+                        logger.directives.push((target.trim().to_string(), level));
+                    }
+                }
+                None => match directive.parse() {
+                    Ok(level) => logger.default = level,
+                    Err(_) => logger
+                        .directives
+                        .push((directive.to_string(), LevelFilter::Trace)),
+                },
+            }
+        }
+        logger
+            .directives
+            .sort_by_key(|(target, _)| std::cmp::Reverse(target.len()));
+
+        logger
+    }
+
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .find(|(prefix, _)| target.starts_with(prefix.as_str()))
+            .map(|(_, level)| *level)
+            .unwrap_or(self.default)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, |max, level| max.max(level))
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{:<5} {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_level() {
+        assert_eq!(Logger::parse("").level_for("rotten"), LevelFilter::Error);
+        assert_eq!(Logger::parse("warn").level_for("rotten"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn most_specific_target_wins() {
+        let logger = Logger::parse("info, rotten=warn, rotten::trace=debug");
+        assert_eq!(logger.level_for("rotten::trace"), LevelFilter::Debug);
+        assert_eq!(logger.level_for("rotten::parser"), LevelFilter::Warn);
+        assert_eq!(logger.level_for("other"), LevelFilter::Info);
+        assert_eq!(logger.max_level(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn bare_target_enables_everything() {
+        let logger = Logger::parse("rotten::trace");
+        assert_eq!(logger.level_for("rotten::trace"), LevelFilter::Trace);
+        assert_eq!(logger.level_for("rotten"), LevelFilter::Error);
+    }
+}
@@ -1,33 +1,174 @@
 use std::{
+    cell::RefCell,
     fs::File,
-    io::{self, Read, Write},
-    path::PathBuf,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    process,
+    rc::Rc,
+    thread,
+    time::{Duration, Instant},
 };
 
-use clap::Parser;
-use log::error;
+use clap::{Parser, Subcommand};
 
-use crate::{interpreter::Interpreter, token::value::TokenValue};
+use crate::{
+    coverage::{Coverage, Summary},
+    debugger::Debugger,
+    interpreter::{
+        Interpreter,
+        limits::{DEFAULT_MAX_DEPTH, Limits},
+    },
+    logger::Logger,
+    profiler::Profiler,
+    repl::Repl,
+    token::value::TokenValue,
+    tracer::Tracer,
+};
 
+mod coverage;
+mod dap;
+mod debugger;
 mod interpreter;
 mod lexer;
+mod logger;
 mod memory;
 mod parser;
+mod profiler;
+mod repl;
 mod token;
+mod tracer;
+
+const STACK_SIZE: usize = 64 * 1024 * 1024;
 
 #[derive(Debug, Parser)]
-#[command(version, about = "A rotten language trash interpreter", long_about = None)]
+#[command(
+    version,
+    about = "A rotten language trash interpreter",
+    long_about = None,
+    args_conflicts_with_subcommands = true
+)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Path to the .rot file to execute.
     /// When omitted the REPL will be started.
     script: Option<PathBuf>,
+
+    /// Log every statement executed and every call with its arguments and return value.
+    /// Statements are logged at trace level and calls at debug level, filter with RUST_LOG.
+    #[arg(long, requires = "script")]
+    trace: bool,
+
+    /// Stop the script once it executed more statements than this.
+    #[arg(long, value_name = "COUNT", requires = "script")]
+    max_statements: Option<usize>,
+
+    /// Deepest call nesting allowed before the script fails with a stack overflow.
+    #[arg(long, value_name = "DEPTH", requires = "script", default_value_t = DEFAULT_MAX_DEPTH)]
+    max_depth: usize,
+
+    /// Stop the script once the values it keeps take more than this many bytes.
+    #[arg(long, value_name = "BYTES", requires = "script")]
+    max_memory: Option<usize>,
+
+    /// Run the garbage collector before every allocation, to debug the collector itself.
+    #[arg(long, requires = "script")]
+    gc_stress: bool,
+
+    /// Initialize instances with the method named after their class instead of `init`.
+    #[arg(long, requires = "script")]
+    legacy_init: bool,
+
+    /// Fail when reading a property that no class declares instead of returning nil.
+    #[arg(long, requires = "script")]
+    strict: bool,
+
+    /// Stop the script once it ran for this many milliseconds.
+    #[arg(long, value_name = "MS", requires = "script")]
+    timeout: Option<u64>,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run a script under the interactive debugger.
+    Debug {
+        /// Path to the .rot file to debug.
+        script: PathBuf,
+    },
+    /// Serve the Debug Adapter Protocol over stdio for editors.
+    Dap,
+    /// Run a script and report where it spent its time.
+    Profile {
+        /// Path to the .rot file to profile.
+        script: PathBuf,
+
+        /// Also write the call stacks in the collapsed format used by flamegraph tools.
+        #[arg(long, value_name = "FILE")]
+        collapsed: Option<PathBuf>,
+    },
+    /// Run scripts and report which lines and branches they executed.
+    Coverage {
+        /// Paths to the .rot files to run, each in a fresh interpreter.
+        #[arg(required = true)]
+        scripts: Vec<PathBuf>,
+
+        /// Also write the coverage of every script to an LCOV tracefile.
+        #[arg(long, value_name = "FILE")]
+        lcov: Option<PathBuf>,
+    },
 }
 
 fn main() {
     let args = Args::parse();
-    match args.script {
-        Some(file_path) => run_file(file_path),
-        None => run_repl(),
+
+    let filter = match args.trace {
+        true => format!("error,{}=trace", tracer::TARGET),
+        false => "error".to_string(),
+    };
+    if let Err(e) = Logger::init(&filter) {
+        eprintln!("Couldn't install the logger: {}", e);
+    }
+
+    // Every call nests a few visitor frames, leave room for the deepest call chain allowed.
+    let main = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(move || dispatch(args))
+        .expect("Couldn't spawn the interpreter thread");
+    if main.join().is_err() {
+        process::exit(101);
+    }
+}
+
+fn dispatch(args: Args) {
+    let limits = Limits {
+        max_statements: args.max_statements,
+        max_depth: Some(args.max_depth),
+        max_memory: args.max_memory,
+        deadline: args
+            .timeout
+            .map(|timeout| Instant::now() + Duration::from_millis(timeout)),
+        ..Default::default()
+    };
+
+    match (args.command, args.script) {
+        (Some(Command::Debug { script }), _) => debug_file(script),
+        (Some(Command::Dap), _) => {
+            if let Err(e) = dap::serve(io::stdin().lock(), io::stdout()) {
+                eprintln!("{}", e);
+            }
+        }
+        (Some(Command::Profile { script, collapsed }), _) => profile_file(script, collapsed),
+        (Some(Command::Coverage { scripts, lcov }), _) => cover_files(scripts, lcov),
+        (None, Some(file_path)) => run_file(
+            file_path,
+            args.trace,
+            args.gc_stress,
+            args.legacy_init,
+            args.strict,
+            limits,
+        ),
+        (None, None) => Repl::default().run(),
     }
 }
 
@@ -38,10 +179,10 @@ fn run(interpreter: &mut Interpreter, source: String) -> anyhow::Result<Option<T
     interpreter.interpret(&mut stmts)
 }
 
-fn run_file(path: PathBuf) {
+fn read_file(path: &Path) -> String {
     let display = path.display();
 
-    let mut file = match File::open(&path) {
+    let mut file = match File::open(path) {
         Ok(file) => file,
         Err(e) => {
             panic!("Couldn't open {}: {}", display, e)
@@ -54,37 +195,119 @@ fn run_file(path: PathBuf) {
         Err(e) => panic!("Couldn't read {}: {}", display, e),
     }
 
+    content
+}
+
+fn run_file(
+    path: PathBuf,
+    trace: bool,
+    gc_stress: bool,
+    legacy_init: bool,
+    strict: bool,
+    limits: Limits,
+) {
+    let content = read_file(&path);
+
     let mut interpreter = interpreter::Interpreter::default();
+    interpreter.set_limits(limits);
+    interpreter.set_gc_stress(gc_stress);
+    interpreter.set_legacy_init(legacy_init);
+    interpreter.set_strict(strict);
+    if trace {
+        interpreter.attach(Box::new(Tracer));
+    }
     if let Err(e) = run(&mut interpreter, content) {
         panic!("{}", e)
     }
 }
 
-fn run_repl() {
-    println!("Welcome to rotten v{}", env!("CARGO_PKG_VERSION"));
+fn debug_file(path: PathBuf) {
+    let content = read_file(&path);
 
     let mut interpreter = interpreter::Interpreter::default();
-    loop {
-        print!("> ");
-        io::stdout().flush().expect("Couldn't flush stdout");
+    interpreter.attach(Box::new(Debugger::new(
+        &content,
+        io::stdin().lock(),
+        io::stdout(),
+    )));
+    if let Err(e) = run(&mut interpreter, content) {
+        println!("{}", e);
+    }
+}
+
+fn profile_file(path: PathBuf, collapsed: Option<PathBuf>) {
+    let content = read_file(&path);
+
+    let profiler = Rc::new(RefCell::new(Profiler::default()));
+    let mut interpreter = interpreter::Interpreter::default();
+    interpreter.attach(Box::new(profiler.clone()));
+    if let Err(e) = run(&mut interpreter, content) {
+        println!("{}", e);
+    }
+    interpreter.detach();
 
-        let mut line = String::new();
-        match io::stdin().read_line(&mut line) {
-            Ok(_) => {}
+    let profiler = profiler.borrow();
+    if let Err(e) = profiler.report(&mut io::stdout()) {
+        eprintln!("Couldn't write the report: {}", e);
+    }
+
+    if let Some(collapsed) = collapsed {
+        let written = File::create(&collapsed)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut file| profiler.write_collapsed(&mut file));
+        if let Err(e) = written {
+            eprintln!("Couldn't write {}: {}", collapsed.display(), e);
+        }
+    }
+}
+
+fn cover_files(paths: Vec<PathBuf>, lcov: Option<PathBuf>) {
+    let mut reports = Vec::new();
+    for path in paths {
+        let content = read_file(&path);
+
+        let tokens = match lexer::run(content) {
+            Ok(tokens) => tokens,
             Err(e) => {
-                error!("Couldn't read input: {}", e);
+                println!("{}", e);
                 continue;
             }
-        }
+        };
+        let mut stmts = parser::Parser::new(tokens).parse();
 
-        if line.trim() == ".exit" {
-            break;
+        let coverage = Rc::new(RefCell::new(Coverage::new(&stmts)));
+        let mut interpreter = interpreter::Interpreter::default();
+        interpreter.attach(Box::new(coverage.clone()));
+        if let Err(e) = interpreter.interpret(&mut stmts) {
+            println!("{}", e);
         }
+        interpreter.detach();
+
+        reports.push((path, coverage.take()));
+    }
+
+    let mut stdout = io::stdout();
+    let summary = Summary::write_header(&mut stdout).and_then(|_| {
+        reports.iter().try_for_each(|(path, coverage)| {
+            coverage
+                .summary()
+                .write_row(&path.display().to_string(), &mut stdout)
+        })
+    });
+    if let Err(e) = summary {
+        eprintln!("Couldn't write the summary: {}", e);
+    }
 
-        match run(&mut interpreter, line) {
-            Ok(Some(val)) => println!("-> {}", val),
-            Err(e) => println!("{}", e),
-            _ => {}
+    if let Some(lcov) = lcov {
+        let written = File::create(&lcov)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut file| {
+                reports
+                    .iter()
+                    .try_for_each(|(path, coverage)| coverage.write_lcov(path, &mut file))
+            });
+        if let Err(e) = written {
+            eprintln!("Couldn't write {}: {}", lcov.display(), e);
         }
     }
 }
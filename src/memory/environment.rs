@@ -15,7 +15,15 @@ impl Environment {
     }
 
     pub fn get(&self, name: &Token) -> Option<TokenValue> {
-        self.data.get(&name.lexeme).map(|val| val.to_owned())
+        self.lookup(&name.lexeme)
+    }
+
+    pub fn lookup(&self, name: &str) -> Option<TokenValue> {
+        self.data.get(name).map(|val| val.to_owned())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &TokenValue)> {
+        self.data.iter()
     }
 
     pub fn assign(&mut self, name: &Token, value: TokenValue) -> Result<()> {
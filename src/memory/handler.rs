@@ -1,3 +1,5 @@
+use std::{cell::RefCell, mem, ops::Range, rc::Rc};
+
 use anyhow::Result;
 
 use crate::{
@@ -5,15 +7,22 @@ use crate::{
     token::{Token, value::TokenValue},
 };
 
+/// Environment on the stack, shared with the closures created while it is in scope.
+pub type Scope = Rc<RefCell<Environment>>;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct EnvironmentHandler {
-    environments: Vec<Environment>,
+    environments: Vec<Scope>,
+    /// Environments names are resolved in besides the globals. Those below belong to callers
+    /// of a closure, which can't see their locals, those above to calls made after it.
+    visible: Range<usize>,
 }
 
 impl Default for EnvironmentHandler {
     fn default() -> Self {
         let mut handler = Self {
             environments: Default::default(),
+            visible: 1..usize::MAX,
         };
         handler.create_environment();
 
@@ -26,6 +35,33 @@ impl EnvironmentHandler {
         self.environments.push(Default::default());
     }
 
+    /// Pushes an environment created earlier, e.g. one a closure captured.
+    pub fn push_environment(&mut self, scope: Scope) {
+        self.environments.push(scope);
+    }
+
+    /// Hides the environments from the globals up to `start` from lookups, returning the
+    /// previous limit so it can be restored once the closure is done.
+    pub fn isolate(&mut self, start: usize) -> Range<usize> {
+        self.restrict(start..usize::MAX)
+    }
+
+    /// Resolves names only in the environments in `visible` and the globals, returning the
+    /// previous limit.
+    pub fn restrict(&mut self, visible: Range<usize>) -> Range<usize> {
+        mem::replace(&mut self.visible, visible.start.max(1)..visible.end)
+    }
+
+    /// Environments names are resolved in, innermost first.
+    fn visible(&self) -> impl Iterator<Item = &Scope> {
+        let end = self.visible.end.min(self.environments.len());
+        let start = self.visible.start.min(end);
+        self.environments[start..end]
+            .iter()
+            .rev()
+            .chain(self.environments.first())
+    }
+
     pub fn delete_environment(&mut self) -> Result<()> {
         if self.environments.len() <= 1 {
             return Err(anyhow::anyhow!("Need at least one environment"));
@@ -35,10 +71,14 @@ impl EnvironmentHandler {
         Ok(())
     }
 
+    pub fn environments(&self) -> &[Scope] {
+        &self.environments
+    }
+
     pub fn define(&mut self, name: String, value: TokenValue) -> Result<()> {
         match self.environments.last_mut() {
             Some(env) => {
-                env.define(name, value);
+                env.borrow_mut().define(name, value);
                 Ok(())
             }
             None => Err(anyhow::anyhow!("Need at least one environment")),
@@ -46,8 +86,8 @@ impl EnvironmentHandler {
     }
 
     pub fn get(&self, name: &Token) -> Option<TokenValue> {
-        for env in self.environments.iter().rev() {
-            if let Some(val) = env.get(name) {
+        for env in self.visible() {
+            if let Some(val) = env.borrow().get(name) {
                 return Some(val.to_owned());
             }
         }
@@ -55,9 +95,44 @@ impl EnvironmentHandler {
         None
     }
 
+    pub fn lookup(&self, name: &str) -> Option<TokenValue> {
+        self.visible().find_map(|env| env.borrow().lookup(name))
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .environments
+            .iter()
+            .flat_map(|env| {
+                env.borrow()
+                    .iter()
+                    .map(|(name, _)| name.to_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    pub fn globals(&self) -> Vec<(String, TokenValue)> {
+        let mut globals: Vec<(String, TokenValue)> = match self.environments.first() {
+            Some(env) => env
+                .borrow()
+                .iter()
+                .map(|(name, value)| (name.to_owned(), value.to_owned()))
+                .collect(),
+            None => Vec::new(),
+        };
+        globals.sort_by(|a, b| a.0.cmp(&b.0));
+
+        globals
+    }
+
     pub fn assign(&mut self, name: &Token, value: TokenValue) -> Result<()> {
-        for env in self.environments.iter_mut().rev() {
-            if env.assign(name, value.clone()).is_ok() {
+        for env in self.visible() {
+            if env.borrow_mut().assign(name, value.clone()).is_ok() {
                 return Ok(());
             }
         }
@@ -65,3 +140,84 @@ impl EnvironmentHandler {
         anyhow::bail!("Undefined variable")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::testing::run;
+
+    #[test]
+    fn captures_variables_in_anonymous_functions() {
+        let output = run("fun counter() {
+  var count = 0;
+  return fun () { count = count + 1; return count; };
+}
+var first = counter();
+var second = counter();
+first();
+print(first());
+print(second());
+fun outer() {
+  var total = 0;
+  fun add(n) { total = total + n; }
+  add(2);
+  add(3);
+  print(total);
+  return (scale) => total * scale;
+}
+print(outer()(10));
+fun apply(f, x) { return f(x); }
+print(apply((n) => { return n + 1; }, 1));
+print((() => \"done\")());
+print((a, b) => a + b);
+");
+        assert_eq!(output, "2\n1\n5\n50\n2\ndone\n<fn lambda/2>\n");
+    }
+
+    #[test]
+    fn resolves_names_where_functions_are_declared() {
+        let output = run("var x = 1;
+fun show() { return x; }
+fun caller() { var x = 2; return show(); }
+print(caller());
+fun make() { var n = 10; return () => x + n; }
+fun other() { var x = 100; var n = 1000; return make()(); }
+print(other());
+");
+        assert_eq!(output, "1\n11\n");
+    }
+
+    #[test]
+    fn methods_capture_variables_of_enclosing_functions() {
+        let output = run("fun make() {
+  var secret = 42;
+  class Box { value = secret + 1; get() { return secret; } }
+  return Box;
+}
+var box = make()();
+print(box.get());
+print(box.value);
+fun mix() {
+  var greeting = \"hi\";
+  trait Greets { greet() { return greeting; } }
+  class Greeter with Greets {}
+  return Greeter;
+}
+print(mix()().greet());
+");
+        assert_eq!(output, "42\n43\nhi\n");
+    }
+
+    #[test]
+    fn functions_in_methods_use_private_members() {
+        let output = run("class Vault {
+  #secret = 7;
+  viaLambda() { var f = () => this.#secret; return f(); }
+  viaFunction() { fun read() { return this.#secret; } return read(); }
+}
+var vault = Vault();
+print(vault.viaLambda());
+print(vault.viaFunction());
+");
+        assert_eq!(output, "7\n7\n");
+    }
+}
@@ -0,0 +1,538 @@
+use std::{
+    mem,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    memory::{handler::Scope, usage::Usage},
+    token::value::{
+        TokenValue, class::Class, function::Function, instance::Instance, traits::Trait,
+    },
+};
+
+/// Fewest live objects that trigger a collection.
+const MIN_THRESHOLD: usize = 256;
+
+/// Reference to an object owned by the [`Heap`]. Handles are only valid while the object is
+/// reachable from the roots, a collection reuses the slots of everything else.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Handle(usize);
+
+#[derive(Debug, Clone)]
+pub enum Object {
+    Class(Box<Class>),
+    Instance(Instance),
+    Trait(Trait),
+    List(Vec<TokenValue>),
+    /// Scopes a function was created in, which it keeps using after they end.
+    Closure(Vec<Scope>),
+}
+
+impl Object {
+    /// Handles the object keeps alive.
+    pub fn references(&self) -> Vec<Handle> {
+        let mut references = Vec::new();
+        match self {
+            Object::Class(class) => {
+                references.extend(class.superclass);
+                references.extend(&class.traits);
+                references.extend(class.closure);
+                for method in class.functions() {
+                    references.extend(function_references(method));
+                }
+                for (_, value) in class.statics.iter() {
+                    references.extend(value_references(value));
+                }
+            }
+            Object::Instance(instance) => {
+                references.push(instance.class);
+                for (_, value) in instance.all_fields() {
+                    references.extend(value_references(value));
+                }
+            }
+            Object::Closure(scopes) => {
+                for scope in scopes {
+                    for (_, value) in scope.borrow().iter() {
+                        references.extend(value_references(value));
+                    }
+                }
+            }
+            Object::Trait(definition) => {
+                for method in definition.methods() {
+                    references.extend(function_references(method));
+                }
+            }
+            Object::List(items) => {
+                for item in items {
+                    references.extend(value_references(item));
+                }
+            }
+        }
+        references
+    }
+
+    /// Approximate number of bytes the object takes.
+    pub fn size(&self) -> usize {
+        match self {
+            Object::Class(class) => {
+                mem::size_of::<Class>()
+                    + class.name.capacity()
+                    + class.functions().count() * mem::size_of::<Function>()
+                    + class
+                        .statics
+                        .iter()
+                        .map(|(name, value)| name.capacity() + Usage::shallow(value))
+                        .sum::<usize>()
+            }
+            Object::Instance(instance) => {
+                mem::size_of::<Instance>()
+                    + instance
+                        .all_fields()
+                        .map(|(name, value)| name.capacity() + Usage::shallow(value))
+                        .sum::<usize>()
+            }
+            Object::Trait(definition) => {
+                mem::size_of::<Trait>()
+                    + definition.name.capacity()
+                    + definition.methods().count() * mem::size_of::<Function>()
+            }
+            Object::List(items) => {
+                mem::size_of::<Vec<TokenValue>>() + items.iter().map(Usage::shallow).sum::<usize>()
+            }
+            // The scopes are shared with the stack and other closures, only count the
+            // references to them. Their bindings are counted once when usage is measured.
+            Object::Closure(scopes) => mem::size_of_val(scopes.as_slice()),
+        }
+    }
+}
+
+/// Handles a value keeps alive.
+pub fn value_references(value: &TokenValue) -> Vec<Handle> {
+    match value {
+        TokenValue::Class(handle)
+        | TokenValue::Instance(handle)
+        | TokenValue::Trait(handle)
+        | TokenValue::List(handle) => vec![*handle],
+        TokenValue::Function(function) => function_references(function),
+        _ => Vec::new(),
+    }
+}
+
+fn function_references(function: &Function) -> Vec<Handle> {
+    function
+        .data
+        .this
+        .into_iter()
+        .chain(function.data.class)
+        .chain(function.data.closure)
+        .chain(function.data.defaults.iter().flat_map(value_references))
+        .collect()
+}
+
+/// What the collector did so far.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct GcStats {
+    pub collections: usize,
+    pub objects_freed: usize,
+    pub bytes_freed: usize,
+    pub pause: Duration,
+}
+
+/// Owns the classes and instances created by scripts and frees the unreachable ones with a
+/// mark-and-sweep collector. The heap doesn't know the roots, whoever collects passes them.
+#[derive(Debug)]
+pub struct Heap {
+    objects: Vec<Option<Object>>,
+    free: Vec<usize>,
+    /// Live objects above which the next allocation should collect first.
+    threshold: usize,
+    /// Collect on every allocation, to surface values that were not rooted.
+    stress: bool,
+    stats: GcStats,
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            objects: Vec::new(),
+            free: Vec::new(),
+            threshold: MIN_THRESHOLD,
+            stress: false,
+            stats: GcStats::default(),
+        }
+    }
+}
+
+impl Heap {
+    pub fn alloc(&mut self, object: Object) -> Handle {
+        match self.free.pop() {
+            Some(slot) => {
+                self.objects[slot] = Some(object);
+                Handle(slot)
+            }
+            None => {
+                self.objects.push(Some(object));
+                Handle(self.objects.len() - 1)
+            }
+        }
+    }
+
+    pub fn should_collect(&self) -> bool {
+        self.stress || self.len() >= self.threshold
+    }
+
+    pub fn set_stress(&mut self, stress: bool) {
+        self.stress = stress;
+    }
+
+    /// Live objects.
+    pub fn len(&self) -> usize {
+        self.objects.len() - self.free.len()
+    }
+
+    pub fn stats(&self) -> &GcStats {
+        &self.stats
+    }
+
+    pub fn get(&self, handle: Handle) -> &Object {
+        self.objects[handle.0]
+            .as_ref()
+            .expect("handle to a collected object")
+    }
+
+    pub fn class(&self, handle: Handle) -> &Class {
+        match self.get(handle) {
+            Object::Class(class) => class,
+            object => panic!("expected a class, found {:?}", object),
+        }
+    }
+
+    pub fn class_mut(&mut self, handle: Handle) -> &mut Class {
+        match self.objects[handle.0].as_mut() {
+            Some(Object::Class(class)) => class,
+            object => panic!("expected a class, found {:?}", object),
+        }
+    }
+
+    pub fn instance(&self, handle: Handle) -> &Instance {
+        match self.get(handle) {
+            Object::Instance(instance) => instance,
+            object => panic!("expected an instance, found {:?}", object),
+        }
+    }
+
+    pub fn get_trait(&self, handle: Handle) -> &Trait {
+        match self.get(handle) {
+            Object::Trait(definition) => definition,
+            object => panic!("expected a trait, found {:?}", object),
+        }
+    }
+
+    pub fn get_trait_mut(&mut self, handle: Handle) -> &mut Trait {
+        match self.objects[handle.0].as_mut() {
+            Some(Object::Trait(definition)) => definition,
+            object => panic!("expected a trait, found {:?}", object),
+        }
+    }
+
+    pub fn closure(&self, handle: Handle) -> &[Scope] {
+        match self.get(handle) {
+            Object::Closure(scopes) => scopes,
+            object => panic!("expected a closure, found {:?}", object),
+        }
+    }
+
+    pub fn list(&self, handle: Handle) -> &[TokenValue] {
+        match self.get(handle) {
+            Object::List(items) => items,
+            object => panic!("expected a list, found {:?}", object),
+        }
+    }
+
+    pub fn list_mut(&mut self, handle: Handle) -> &mut Vec<TokenValue> {
+        match self.objects[handle.0].as_mut() {
+            Some(Object::List(items)) => items,
+            object => panic!("expected a list, found {:?}", object),
+        }
+    }
+
+    /// Abstract methods of `class` and its superclasses that no class in between defines,
+    /// sorted by name. Accessors don't implement an abstract method of the same name.
+    pub fn missing_methods(&self, class: Handle) -> Vec<String> {
+        let mut defined: Vec<&str> = Vec::new();
+        let mut missing = Vec::new();
+        let mut current = Some(class);
+        while let Some(handle) = current {
+            let class = self.class(handle);
+            missing.extend(
+                class
+                    .abstracts
+                    .iter()
+                    .filter(|name| !defined.contains(&name.as_str()))
+                    .cloned(),
+            );
+            defined.extend(class.methods().map(|method| method.data.name.as_str()));
+            current = class.superclass;
+        }
+        missing.sort();
+        missing.dedup();
+
+        missing
+    }
+
+    /// Whether `class` or one of its superclasses composes the trait `definition`.
+    pub fn implements(&self, class: Handle, definition: Handle) -> bool {
+        let class = self.class(class);
+        class.traits.contains(&definition)
+            || class
+                .superclass
+                .is_some_and(|superclass| self.implements(superclass, definition))
+    }
+
+    pub fn instance_mut(&mut self, handle: Handle) -> &mut Instance {
+        match self.objects[handle.0].as_mut() {
+            Some(Object::Instance(instance)) => instance,
+            object => panic!("expected an instance, found {:?}", object),
+        }
+    }
+
+    /// Looks `name` up in the methods of `class` and then of its superclasses.
+    pub fn find_method(&self, class: Handle, name: &str) -> Option<Function> {
+        self.find_function(class, |class| class.method(name))
+    }
+
+    pub fn find_getter(&self, class: Handle, name: &str) -> Option<Function> {
+        self.find_function(class, |class| class.getter(name))
+    }
+
+    pub fn find_setter(&self, class: Handle, name: &str) -> Option<Function> {
+        self.find_function(class, |class| class.setter(name))
+    }
+
+    fn find_function(
+        &self,
+        class: Handle,
+        lookup: impl Fn(&Class) -> Option<&Function>,
+    ) -> Option<Function> {
+        let class = self.class(class);
+        match lookup(class) {
+            Some(function) => Some(function.to_owned()),
+            None => class
+                .superclass
+                .and_then(|superclass| self.find_function(superclass, lookup)),
+        }
+    }
+
+    /// Looks the static member `name` up in `class` and then in its superclasses.
+    pub fn find_static(&self, class: Handle, name: &str) -> Option<TokenValue> {
+        let class = self.class(class);
+        match class.statics.lookup(name) {
+            Some(value) => Some(value),
+            None => class
+                .superclass
+                .and_then(|superclass| self.find_static(superclass, name)),
+        }
+    }
+
+    /// Whether `ancestor` is `class` or one of its superclasses.
+    pub fn inherits(&self, class: Handle, ancestor: Handle) -> bool {
+        class == ancestor
+            || self
+                .class(class)
+                .superclass
+                .is_some_and(|superclass| self.inherits(superclass, ancestor))
+    }
+
+    /// Whether `class` or one of its superclasses declares a member named `name`.
+    pub fn declares(&self, class: Handle, name: &str) -> bool {
+        let class = self.class(class);
+        class.declares(name)
+            || class
+                .superclass
+                .is_some_and(|superclass| self.declares(superclass, name))
+    }
+
+    /// Names of the static members of `class` and its superclasses.
+    pub fn static_names(&self, class: Handle) -> Vec<String> {
+        let class = self.class(class);
+        let mut names: Vec<String> = class
+            .statics
+            .iter()
+            .map(|(name, _)| name.to_owned())
+            .collect();
+        if let Some(superclass) = class.superclass {
+            names.extend(self.static_names(superclass));
+        }
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    /// Names of the methods of `class` and its superclasses, without accessors.
+    pub fn method_names(&self, class: Handle) -> Vec<String> {
+        self.inherited_names(class, false)
+    }
+
+    /// Names of the methods and accessors of `class` and its superclasses.
+    pub fn function_names(&self, class: Handle) -> Vec<String> {
+        self.inherited_names(class, true)
+    }
+
+    fn inherited_names(&self, class: Handle, accessors: bool) -> Vec<String> {
+        let class = self.class(class);
+        let mut names: Vec<String> = match accessors {
+            true => class
+                .functions()
+                .map(|method| method.data.name.to_owned())
+                .collect(),
+            false => class
+                .methods()
+                .map(|method| method.data.name.to_owned())
+                .collect(),
+        };
+        if let Some(superclass) = class.superclass {
+            names.extend(self.inherited_names(superclass, accessors));
+        }
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    /// Names of the fields of `instance` and the methods of its class, without the private
+    /// ones.
+    pub fn member_names(&self, instance: Handle) -> Vec<String> {
+        let instance = self.instance(instance);
+        let mut names: Vec<String> = instance
+            .fields
+            .iter()
+            .map(|(name, _)| name.to_owned())
+            .collect();
+        names.extend(self.function_names(instance.class));
+        names.retain(|name| !name.starts_with('#'));
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    /// Frees every object not reachable from `roots` and returns how many were freed.
+    pub fn collect(&mut self, roots: impl IntoIterator<Item = Handle>) -> usize {
+        let start = Instant::now();
+
+        let mut marked = vec![false; self.objects.len()];
+        let mut pending: Vec<Handle> = roots.into_iter().collect();
+        while let Some(handle) = pending.pop() {
+            if !mem::replace(&mut marked[handle.0], true) {
+                pending.extend(self.get(handle).references());
+            }
+        }
+
+        let mut freed = 0;
+        for (slot, marked) in marked.into_iter().enumerate() {
+            if !marked && let Some(object) = self.objects[slot].take() {
+                self.stats.bytes_freed += object.size();
+                self.free.push(slot);
+                freed += 1;
+            }
+        }
+
+        self.threshold = (self.len() * 2).max(MIN_THRESHOLD);
+        self.stats.collections += 1;
+        self.stats.objects_freed += freed;
+        self.stats.pause += start.elapsed();
+
+        freed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{
+        Interpreter,
+        testing::{capture, fail, run},
+    };
+
+    #[test]
+    fn reclaims_unreachable_objects_and_cycles() {
+        let mut interpreter = Interpreter::default();
+        capture(
+            &mut interpreter,
+            "class Node {
+  link(other) { this.other = other; }
+  callback() { return this; }
+}
+var kept = Node();
+kept.self = kept;
+for (var i = 0; i < 1000; i = i + 1) {
+  var a = Node();
+  var b = Node();
+  a.link(b);
+  b.link(a);
+  a.method = a.callback;
+}",
+        )
+        .unwrap();
+        // Collections ran while the loop was creating instances.
+        assert!(interpreter.heap().stats().collections > 0);
+        assert!(interpreter.heap().len() < 600);
+
+        interpreter.collect_garbage();
+        assert_eq!(interpreter.heap().len(), 2);
+        assert_eq!(interpreter.heap().stats().objects_freed, 2000);
+        assert_eq!(
+            capture(&mut interpreter, "print(kept.self.self);").unwrap(),
+            "Node { self: Node {...} }\n"
+        );
+    }
+
+    #[test]
+    fn keeps_temporaries_alive_under_stress() {
+        let mut interpreter = Interpreter::default();
+        interpreter.set_gc_stress(true);
+        let output = capture(
+            &mut interpreter,
+            "class Pair {
+  init(first, second) { this.first = first; this.second = second; }
+}
+class Leaf < Pair {
+  init(name) { this.first = name; }
+}
+fun make(name) { var leaf = Leaf(name); return leaf; }
+var pair = Pair(make(\"a\"), Pair(make(\"b\"), make(\"c\")));
+print(pair.second.first.first + pair.first.first);
+var stats = gc_stats();
+print(stats.collections > 0);",
+        );
+        assert_eq!(output.unwrap(), "ba\ntrue\n");
+    }
+
+    #[test]
+    fn rejects_classes_with_abstract_methods() {
+        let source = "class Plugin {
+  abstract start(config);
+  abstract stop();
+  run() { this.start(1); return this.stop(); }
+}
+class Partial < Plugin {
+  start(config) {}
+}
+class Full < Partial {
+  stop() { return \"stopped\"; }
+}
+print(Full().run());
+";
+        assert_eq!(run(source), "stopped\n");
+
+        let error = |script: &str| fail(&format!("{}{}", source, script));
+        assert!(error("Plugin();").contains(
+            "[13:8] Interpreter Error: Can't instantiate Plugin, abstract methods not implemented: start, stop"
+        ));
+        assert!(error("Partial();").contains("abstract methods not implemented: stop"));
+        assert!(
+            error("class Getter < Partial { get stop { return 1; } }\nGetter();")
+                .contains("abstract methods not implemented: stop")
+        );
+    }
+}
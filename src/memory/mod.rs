@@ -1,2 +1,4 @@
 pub mod environment;
 pub mod handler;
+pub mod heap;
+pub mod usage;
@@ -0,0 +1,75 @@
+use std::{cell::RefCell, collections::HashSet, mem, rc::Rc};
+
+use crate::{
+    memory::{
+        environment::Environment,
+        handler::Scope,
+        heap::{Handle, Heap, Object, value_references},
+    },
+    token::value::TokenValue,
+};
+
+/// Approximate size of the values reachable from environments. Objects on the heap and
+/// scopes shared between the stack and closures are counted once, so instances referencing
+/// each other don't inflate the total.
+#[derive(Debug)]
+pub struct Usage<'a> {
+    heap: &'a Heap,
+    seen: HashSet<Handle>,
+    scopes: HashSet<*const RefCell<Environment>>,
+    bytes: usize,
+}
+
+impl<'a> Usage<'a> {
+    pub fn measure(heap: &'a Heap, environments: impl IntoIterator<Item = &'a Scope>) -> usize {
+        let mut usage = Self {
+            heap,
+            seen: HashSet::new(),
+            scopes: HashSet::new(),
+            bytes: 0,
+        };
+        for environment in environments {
+            usage.bindings(environment);
+            for (_, value) in environment.borrow().iter() {
+                usage.references(value);
+            }
+        }
+        usage.bytes
+    }
+
+    /// Bytes a value takes by itself, without the objects it references.
+    pub fn shallow(value: &TokenValue) -> usize {
+        mem::size_of::<TokenValue>()
+            + match value {
+                TokenValue::String(text) => text.capacity(),
+                _ => 0,
+            }
+    }
+
+    /// Counts the names and values bound in `scope`, unless it was counted already.
+    fn bindings(&mut self, scope: &Scope) {
+        if !self.scopes.insert(Rc::as_ptr(scope)) {
+            return;
+        }
+        self.bytes += scope
+            .borrow()
+            .iter()
+            .map(|(name, value)| name.capacity() + Self::shallow(value))
+            .sum::<usize>();
+    }
+
+    fn references(&mut self, value: &TokenValue) {
+        let mut pending = value_references(value);
+        while let Some(handle) = pending.pop() {
+            if self.seen.insert(handle) {
+                let object = self.heap.get(handle);
+                self.bytes += object.size();
+                // Captured scopes hold values of their own besides the references to them.
+                if let Object::Closure(scopes) = object {
+                    scopes.iter().for_each(|scope| self.bindings(scope));
+                }
+                pending.extend(object.references());
+            }
+        }
+    }
+}
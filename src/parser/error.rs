@@ -10,6 +10,9 @@ pub enum ParserErrorMessage {
     UnexpectedTokenType,
     ExpectToken(TokenType),
     InvalidAssignment,
+    ReturnFromInitializer,
+    RequiredAfterDefault,
+    PositionalAfterNamed,
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +31,15 @@ impl ParserError {
             ParserErrorMessage::UnexpectedTokenType => "Unexpected token type".to_string(),
             ParserErrorMessage::ExpectToken(kind) => format!("'{}' expected", kind),
             ParserErrorMessage::InvalidAssignment => "Invalid assignment target".to_string(),
+            ParserErrorMessage::ReturnFromInitializer => {
+                "Can't return a value from an initializer".to_string()
+            }
+            ParserErrorMessage::RequiredAfterDefault => {
+                "A parameter without a default can't follow one with a default".to_string()
+            }
+            ParserErrorMessage::PositionalAfterNamed => {
+                "A positional argument can't follow a named one".to_string()
+            }
         }
     }
 }
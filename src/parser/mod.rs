@@ -1,38 +1,58 @@
-use std::ffi::FromBytesUntilNulError;
+use std::mem;
 
 use anyhow::Result;
 
 use crate::{
     parser::{
         error::{ParserError, ParserErrorMessage},
-        node::{expression::Expression, statement::Statement},
+        node::{
+            expression::{Expression, NamedArgument},
+            statement::{AbstractMethod, ClassBody, Parameter, Statement},
+        },
     },
     token::{Token, kind::TokenType, value::TokenValue},
 };
 
 mod error;
 pub mod node;
+pub mod printer;
 
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    /// Whether the innermost function being parsed is an `init` method.
+    in_initializer: bool,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, current: 0 }
+        Self {
+            tokens,
+            current: 0,
+            in_initializer: false,
+        }
     }
 
     pub fn parse(&mut self) -> Vec<Statement> {
+        let (statements, errors) = self.parse_with_errors();
+        for e in errors {
+            println!("{}", e);
+        }
+
+        statements
+    }
+
+    /// Parses every statement it can, handing back the errors instead of printing them.
+    pub fn parse_with_errors(&mut self) -> (Vec<Statement>, Vec<anyhow::Error>) {
         let mut statements = Vec::new();
+        let mut errors = Vec::new();
 
         while !self.is_at_end() {
             let statement = match self.declaration() {
                 Ok(stmt) => stmt,
                 Err(e) => {
-                    println!("{}", e);
-                    log::error!("{}", e);
+                    errors.push(e);
                     self.syncronize();
                     continue;
                 }
@@ -40,7 +60,23 @@ impl Parser {
             statements.push(statement);
         }
 
-        statements
+        (statements, errors)
+    }
+
+    /// Parses the tokens as a single expression, e.g. a watch expression typed in a debugger.
+    pub fn parse_expression(&mut self) -> Result<Expression> {
+        let expr = self.expression()?;
+        self.match_tokens(&[TokenType::Semicolon]);
+
+        if !self.is_at_end() {
+            return Err(ParserError {
+                message: ParserErrorMessage::UnexpectedTokenType,
+                token: Some(self.peek()?),
+            }
+            .into());
+        }
+
+        Ok(expr)
     }
 
     fn match_tokens(&mut self, kinds: &[TokenType]) -> bool {
@@ -53,6 +89,13 @@ impl Parser {
         false
     }
 
+    /// Whether the token after the current one is of `kind`.
+    fn check_next(&self, kind: &TokenType) -> bool {
+        self.tokens
+            .get(self.current + 1)
+            .is_some_and(|token| &token.kind == kind)
+    }
+
     fn check(&self, kind: &TokenType) -> bool {
         if self.is_at_end() {
             return false;
@@ -117,6 +160,7 @@ impl Parser {
             let Ok(token) = self.peek() else { continue };
             match token.kind {
                 TokenType::Class
+                | TokenType::Trait
                 | TokenType::Fun
                 | TokenType::Var
                 | TokenType::For
@@ -127,9 +171,9 @@ impl Parser {
                 }
                 _ => {}
             }
-        }
 
-        let _ = self.advance();
+            let _ = self.advance();
+        }
     }
 
     fn consume(&mut self, kind: TokenType) -> Result<Token> {
@@ -169,6 +213,18 @@ impl Parser {
                         value: Box::new(value),
                     });
                 }
+                Expression::Index {
+                    object,
+                    bracket,
+                    index,
+                } => {
+                    return Ok(Expression::SetIndex {
+                        object,
+                        bracket,
+                        index,
+                        value: Box::new(value),
+                    });
+                }
                 _ => {
                     return Err(ParserError {
                         message: ParserErrorMessage::InvalidAssignment,
@@ -179,9 +235,53 @@ impl Parser {
             }
         }
 
+        if self.match_tokens(&[
+            TokenType::PlusEqual,
+            TokenType::MinusEqual,
+            TokenType::StarEqual,
+            TokenType::SlashEqual,
+            TokenType::PercentEqual,
+        ]) {
+            let operator = self.previous()?;
+            let value = self.assignment()?;
+            return Self::compound(expr, operator, value, false);
+        }
+
         Ok(expr)
     }
 
+    /// Updates `target` in place, which has to be something an assignment can set.
+    fn compound(
+        target: Expression,
+        operator: Token,
+        value: Expression,
+        postfix: bool,
+    ) -> Result<Expression> {
+        match target {
+            Expression::Variable { .. } | Expression::Get { .. } | Expression::Index { .. } => {
+                Ok(Expression::Compound {
+                    target: Box::new(target),
+                    operator,
+                    value: Box::new(value),
+                    postfix,
+                })
+            }
+            _ => Err(ParserError {
+                message: ParserErrorMessage::InvalidAssignment,
+                token: Some(operator),
+            }
+            .into()),
+        }
+    }
+
+    /// `++target`, `target--` and the like, which step the target by 1 like `target += 1`.
+    fn increment(target: Expression, operator: Token, postfix: bool) -> Result<Expression> {
+        let one = Expression::Literal {
+            value: TokenValue::Number(1.0),
+        };
+        Self::compound(target, operator, one, postfix)
+    }
+
     fn or(&mut self) -> Result<Expression> {
         let mut expr = self.and()?;
 
@@ -271,7 +371,7 @@ impl Parser {
     fn factor(&mut self) -> Result<Expression> {
         let mut expr = self.unary()?;
 
-        while self.match_tokens(&[TokenType::Slash, TokenType::Star]) {
+        while self.match_tokens(&[TokenType::Slash, TokenType::Star, TokenType::Percent]) {
             let operator = self.previous()?;
             let right = self.unary()?;
             expr = Expression::Binary {
@@ -294,7 +394,18 @@ impl Parser {
             });
         }
 
-        self.call()
+        if self.match_tokens(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            let operator = self.previous()?;
+            let target = self.unary()?;
+            return Self::increment(target, operator, false);
+        }
+
+        let expr = self.call()?;
+        if self.match_tokens(&[TokenType::PlusPlus, TokenType::MinusMinus]) {
+            return Self::increment(expr, self.previous()?, true);
+        }
+
+        Ok(expr)
     }
 
     fn call(&mut self) -> Result<Expression> {
@@ -303,9 +414,26 @@ impl Parser {
         loop {
             if self.match_tokens(&[TokenType::LeftParen]) {
                 let mut arguments = Vec::new();
+                let mut named = Vec::new();
                 if !self.check(&TokenType::RightParen) {
                     loop {
-                        arguments.push(self.expression()?);
+                        if self.check(&TokenType::Identifier) && self.check_next(&TokenType::Colon)
+                        {
+                            let name = self.advance()?;
+                            self.advance()?;
+                            named.push(NamedArgument {
+                                name,
+                                value: self.expression()?,
+                            });
+                        } else if !named.is_empty() {
+                            return Err(ParserError {
+                                message: ParserErrorMessage::PositionalAfterNamed,
+                                token: Some(self.peek()?),
+                            }
+                            .into());
+                        } else {
+                            arguments.push(self.expression()?);
+                        }
                         if !self.match_tokens(&[TokenType::Comma]) {
                             break;
                         }
@@ -318,6 +446,7 @@ impl Parser {
                     callee: Box::new(expr),
                     paren,
                     arguments,
+                    named,
                 }
             } else if self.match_tokens(&[TokenType::Dot]) {
                 let name = self.consume(TokenType::Identifier)?;
@@ -325,6 +454,14 @@ impl Parser {
                     object: Box::new(expr),
                     name,
                 }
+            } else if self.match_tokens(&[TokenType::LeftBracket]) {
+                let index = self.expression()?;
+                let bracket = self.consume(TokenType::RightBracket)?;
+                expr = Expression::Index {
+                    object: Box::new(expr),
+                    bracket,
+                    index: Box::new(index),
+                }
             } else {
                 break;
             }
@@ -385,6 +522,41 @@ impl Parser {
             });
         }
 
+        if self.match_tokens(&[TokenType::Fun]) {
+            let keyword = self.previous()?;
+            let params = self.parameters()?;
+            self.consume(TokenType::LeftBrace)?;
+            let body = self.function_body()?;
+
+            return Ok(Expression::Lambda {
+                keyword,
+                params,
+                body: Box::new(body),
+            });
+        }
+
+        if self.check(&TokenType::LeftParen) && self.arrow_ahead() {
+            let keyword = self.peek()?;
+            let params = self.parameters()?;
+            let arrow = self.consume(TokenType::Arrow)?;
+            let body = if self.match_tokens(&[TokenType::LeftBrace]) {
+                self.function_body()?
+            } else {
+                Statement::Block {
+                    statements: vec![Statement::Return {
+                        keyword: arrow,
+                        value: Some(Box::new(self.expression()?)),
+                    }],
+                }
+            };
+
+            return Ok(Expression::Lambda {
+                keyword,
+                params,
+                body: Box::new(body),
+            });
+        }
+
         if self.match_tokens(&[TokenType::LeftParen]) {
             let expr = self.expression()?;
             self.consume(TokenType::RightParen)?;
@@ -406,8 +578,14 @@ impl Parser {
             return self.class_declaration();
         }
 
-        if self.match_tokens(&[TokenType::Fun]) {
-            return self.function_declaration();
+        if self.match_tokens(&[TokenType::Trait]) {
+            return self.trait_declaration();
+        }
+
+        // `fun (` starts an anonymous function used as an expression statement.
+        if self.check(&TokenType::Fun) && !self.check_next(&TokenType::LeftParen) {
+            self.advance()?;
+            return self.function_declaration(false);
         }
 
         if self.match_tokens(&[TokenType::Var]) {
@@ -428,28 +606,200 @@ impl Parser {
             }))
         }
 
+        let mut traits = Vec::new();
+        if self.match_tokens(&[TokenType::With]) {
+            loop {
+                traits.push(Expression::Variable {
+                    name: self.consume(TokenType::Identifier)?,
+                });
+                if !self.match_tokens(&[TokenType::Comma]) {
+                    break;
+                }
+            }
+        }
+
         self.consume(TokenType::LeftBrace)?;
-        let mut methods = Vec::new();
+        let mut body = ClassBody::default();
         while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
-            methods.push(self.function_declaration()?);
+            if self.match_tokens(&[TokenType::Static]) {
+                body.statics.push(self.static_declaration()?);
+                continue;
+            }
+
+            if self.match_tokens(&[TokenType::Abstract]) {
+                body.abstracts.push(self.abstract_declaration()?);
+                continue;
+            }
+
+            // A name followed by neither parameters nor a property name declares a field.
+            if !self.check_next(&TokenType::LeftParen) && !self.check_next(&TokenType::Identifier) {
+                body.fields.push(self.var_declaration()?);
+                continue;
+            }
+
+            // `get` and `set` only start an accessor when a property name follows, so they
+            // stay usable as method names.
+            let lexeme = self.peek()?.lexeme;
+            if self.check(&TokenType::Identifier) && self.check_next(&TokenType::Identifier) {
+                match lexeme.as_str() {
+                    "get" => {
+                        let _ = self.advance();
+                        body.getters.push(self.getter_declaration()?);
+                        continue;
+                    }
+                    "set" => {
+                        let _ = self.advance();
+                        body.setters.push(self.function_declaration(false)?);
+                        continue;
+                    }
+                    _ => {}
+                }
+            }
+
+            body.methods
+                .push(self.function_declaration(lexeme == "init")?);
         }
         self.consume(TokenType::RightBrace)?;
 
         Ok(Statement::Class {
             name,
             superclass,
-            methods,
+            traits,
+            body,
+        })
+    }
+
+    fn trait_declaration(&mut self) -> Result<Statement> {
+        let name = self.consume(TokenType::Identifier)?;
+
+        self.consume(TokenType::LeftBrace)?;
+        let mut methods = Vec::new();
+        while !self.check(&TokenType::RightBrace) && !self.is_at_end() {
+            methods.push(self.function_declaration(false)?);
+        }
+        self.consume(TokenType::RightBrace)?;
+
+        Ok(Statement::Trait { name, methods })
+    }
+
+    fn getter_declaration(&mut self) -> Result<Statement> {
+        let name = self.consume(TokenType::Identifier)?;
+        self.consume(TokenType::LeftBrace)?;
+        let enclosing = mem::replace(&mut self.in_initializer, false);
+        let body = self.block_statement();
+        self.in_initializer = enclosing;
+
+        Ok(Statement::Function {
+            name,
+            params: Vec::new(),
+            body: Box::new(body?),
+        })
+    }
+
+    /// A static method when the name is followed by parameters, a static field otherwise.
+    fn static_declaration(&mut self) -> Result<Statement> {
+        if self.check_next(&TokenType::LeftParen) {
+            return self.function_declaration(false);
+        }
+
+        self.var_declaration()
+    }
+
+    fn function_declaration(&mut self, initializer: bool) -> Result<Statement> {
+        let name = self.consume(TokenType::Identifier)?;
+        let parameters = self.parameters()?;
+
+        self.consume(TokenType::LeftBrace)?;
+        let enclosing = mem::replace(&mut self.in_initializer, initializer);
+        let body = self.block_statement();
+        self.in_initializer = enclosing;
+        let body = body?;
+
+        Ok(Statement::Function {
+            name,
+            params: parameters,
+            body: Box::new(body),
         })
     }
 
-    fn function_declaration(&mut self) -> Result<Statement> {
+    /// Block of an anonymous function, after its `{`. It may return a value even when the
+    /// function is created inside an initializer.
+    fn function_body(&mut self) -> Result<Statement> {
+        let enclosing = mem::replace(&mut self.in_initializer, false);
+        let body = self.block_statement();
+        self.in_initializer = enclosing;
+
+        body
+    }
+
+    /// Whether the parenthesis at the current token opens the parameters of an arrow
+    /// function, i.e. its matching parenthesis is followed by `=>`.
+    fn arrow_ahead(&self) -> bool {
+        let mut depth = 0;
+        let mut tokens = self.tokens[self.current..].iter();
+        for token in tokens.by_ref() {
+            match token.kind {
+                TokenType::LeftParen => depth += 1,
+                TokenType::RightParen if depth == 1 => {
+                    return tokens
+                        .next()
+                        .is_some_and(|token| token.kind == TokenType::Arrow);
+                }
+                TokenType::RightParen => depth -= 1,
+                TokenType::Semicolon | TokenType::EndOfFile => return false,
+                _ => {}
+            }
+        }
+
+        false
+    }
+
+    /// `abstract name(params);`, after the `abstract` keyword.
+    fn abstract_declaration(&mut self) -> Result<AbstractMethod> {
         let name = self.consume(TokenType::Identifier)?;
+        let params = self.parameters()?;
+        self.consume(TokenType::Semicolon)?;
+
+        Ok(AbstractMethod { name, params })
+    }
 
+    /// Parenthesized, comma separated parameters. Parameters with a default come after the
+    /// required ones, and a rest parameter comes last.
+    fn parameters(&mut self) -> Result<Vec<Parameter>> {
         self.consume(TokenType::LeftParen)?;
-        let mut parameters = Vec::new();
+        let mut parameters: Vec<Parameter> = Vec::new();
         if !self.check(&TokenType::RightParen) {
             loop {
-                parameters.push(self.consume(TokenType::Identifier)?);
+                if self.match_tokens(&[TokenType::Ellipsis]) {
+                    parameters.push(Parameter {
+                        name: self.consume(TokenType::Identifier)?,
+                        default: None,
+                        rest: true,
+                    });
+                    break;
+                }
+
+                let name = self.consume(TokenType::Identifier)?;
+                let default = match self.match_tokens(&[TokenType::Equal]) {
+                    true => Some(self.expression()?),
+                    false => None,
+                };
+                if default.is_none()
+                    && parameters
+                        .last()
+                        .is_some_and(|previous| previous.default.is_some())
+                {
+                    return Err(ParserError {
+                        message: ParserErrorMessage::RequiredAfterDefault,
+                        token: Some(name),
+                    }
+                    .into());
+                }
+                parameters.push(Parameter {
+                    name,
+                    default,
+                    rest: false,
+                });
 
                 if !self.match_tokens(&[TokenType::Comma]) {
                     break;
@@ -458,14 +808,7 @@ impl Parser {
         }
         self.consume(TokenType::RightParen)?;
 
-        self.consume(TokenType::LeftBrace)?;
-        let body = self.block_statement()?;
-
-        Ok(Statement::Function {
-            name,
-            params: parameters,
-            body: Box::new(body),
-        })
+        Ok(parameters)
     }
 
     fn var_declaration(&mut self) -> Result<Statement> {
@@ -505,28 +848,39 @@ impl Parser {
     }
 
     fn return_statement(&mut self) -> Result<Statement> {
+        let keyword = self.previous()?;
         let mut value = None;
         if !self.check(&TokenType::Semicolon) {
+            if self.in_initializer {
+                return Err(ParserError {
+                    message: ParserErrorMessage::ReturnFromInitializer,
+                    token: Some(keyword),
+                }
+                .into());
+            }
             value = Some(Box::new(self.expression()?));
         }
 
         self.consume(TokenType::Semicolon)?;
-        Ok(Statement::Return { value })
+        Ok(Statement::Return { keyword, value })
     }
 
     fn while_statement(&mut self) -> Result<Statement> {
+        let keyword = self.previous()?;
         self.consume(TokenType::LeftParen)?;
         let condition = self.expression()?;
         self.consume(TokenType::RightParen)?;
         let body = self.statement()?;
 
         Ok(Statement::While {
+            keyword,
             condition: Box::new(condition),
             body: Box::new(body),
         })
     }
 
     fn for_statement(&mut self) -> Result<Statement> {
+        let keyword = self.previous()?;
         self.consume(TokenType::LeftParen)?;
 
         let initializer = if self.match_tokens(&[TokenType::Semicolon]) {
@@ -565,6 +919,7 @@ impl Parser {
         }
 
         body = Statement::While {
+            keyword,
             condition,
             body: Box::new(body),
         };
@@ -590,6 +945,7 @@ impl Parser {
     }
 
     fn if_statement(&mut self) -> Result<Statement> {
+        let keyword = self.previous()?;
         self.consume(TokenType::LeftParen)?;
         let condition = self.expression()?;
         self.consume(TokenType::RightParen)?;
@@ -601,6 +957,7 @@ impl Parser {
         }
 
         Ok(Statement::If {
+            keyword,
             condition: Box::new(condition),
             then_branch: Box::new(then_branch),
             else_branch,
@@ -1,4 +1,7 @@
-use crate::token::{Token, value::TokenValue};
+use crate::{
+    parser::node::statement::{Parameter, Statement},
+    token::{Token, TokenPosition, kind::TokenType, value::TokenValue},
+};
 
 #[derive(Debug, Clone)]
 pub enum Expression {
@@ -11,10 +14,20 @@ pub enum Expression {
         operator: Token,
         right: Box<Expression>,
     },
+    /// `target op= value`, or `++target` and `target++` with a value of 1, on a variable,
+    /// property or index. Postfix updates evaluate to the value before the update.
+    Compound {
+        target: Box<Expression>,
+        operator: Token,
+        value: Box<Expression>,
+        postfix: bool,
+    },
     Call {
         callee: Box<Expression>,
         paren: Token,
         arguments: Vec<Expression>,
+        /// Arguments passed by name, after the positional ones.
+        named: Vec<NamedArgument>,
     },
     Get {
         object: Box<Expression>,
@@ -23,6 +36,18 @@ pub enum Expression {
     Grouping {
         expression: Box<Expression>,
     },
+    Index {
+        object: Box<Expression>,
+        bracket: Token,
+        index: Box<Expression>,
+    },
+    /// Anonymous function, `fun (a) { ... }` or `(a) => ...` whose body returns the
+    /// expression. `keyword` is the `fun` token or the opening paren of an arrow.
+    Lambda {
+        keyword: Token,
+        params: Vec<Parameter>,
+        body: Box<Statement>,
+    },
     Literal {
         value: TokenValue,
     },
@@ -36,6 +61,12 @@ pub enum Expression {
         name: Token,
         value: Box<Expression>,
     },
+    SetIndex {
+        object: Box<Expression>,
+        bracket: Token,
+        index: Box<Expression>,
+        value: Box<Expression>,
+    },
     Super {
         keyword: Token,
         method: Token,
@@ -52,6 +83,13 @@ pub enum Expression {
     },
 }
 
+/// Argument passed by the name of a parameter, `name: value`.
+#[derive(Debug, Clone)]
+pub struct NamedArgument {
+    pub name: Token,
+    pub value: Expression,
+}
+
 // We'll update the visitor pattern to work with the new enum variants later
 // For now, we'll keep the existing visitor traits but update them to work with the new structure
 
@@ -63,14 +101,29 @@ pub trait ExpressionVisitor<T> {
         operator: &Token,
         right: &mut Expression,
     ) -> T;
+    fn visit_compound(
+        &mut self,
+        target: &mut Expression,
+        operator: &Token,
+        value: &mut Expression,
+        postfix: bool,
+    ) -> T;
     fn visit_call(
         &mut self,
         callee: &mut Expression,
         paren: &Token,
         arguments: &mut [Expression],
+        named: &mut [NamedArgument],
     ) -> T;
     fn visit_get(&mut self, object: &mut Expression, name: &Token) -> T;
     fn visit_grouping(&mut self, expression: &mut Expression) -> T;
+    fn visit_index(
+        &mut self,
+        object: &mut Expression,
+        bracket: &Token,
+        index: &mut Expression,
+    ) -> T;
+    fn visit_lambda(&mut self, keyword: &Token, params: &[Parameter], body: &mut Statement) -> T;
     fn visit_literal(&mut self, value: &TokenValue) -> T;
     fn visit_logical(
         &mut self,
@@ -79,6 +132,13 @@ pub trait ExpressionVisitor<T> {
         right: &mut Expression,
     ) -> T;
     fn visit_set(&mut self, object: &mut Expression, name: &Token, value: &mut Expression) -> T;
+    fn visit_set_index(
+        &mut self,
+        object: &mut Expression,
+        bracket: &Token,
+        index: &mut Expression,
+        value: &mut Expression,
+    ) -> T;
     fn visit_super(&mut self, keyword: &Token, method: &Token) -> T;
     fn visit_this(&mut self, keyword: &Token) -> T;
     fn visit_unary(&mut self, operator: &Token, right: &mut Expression) -> T;
@@ -94,13 +154,30 @@ impl Expression {
                 operator,
                 right,
             } => visitor.visit_binary(left, operator, right),
+            Expression::Compound {
+                target,
+                operator,
+                value,
+                postfix,
+            } => visitor.visit_compound(target, operator, value, *postfix),
             Expression::Call {
                 callee,
                 paren,
                 arguments,
-            } => visitor.visit_call(callee, paren, arguments),
+                named,
+            } => visitor.visit_call(callee, paren, arguments, named),
             Expression::Get { object, name } => visitor.visit_get(object, name),
             Expression::Grouping { expression } => visitor.visit_grouping(expression),
+            Expression::Index {
+                object,
+                bracket,
+                index,
+            } => visitor.visit_index(object, bracket, index),
+            Expression::Lambda {
+                keyword,
+                params,
+                body,
+            } => visitor.visit_lambda(keyword, params, body),
             Expression::Literal { value } => visitor.visit_literal(value),
             Expression::Logical {
                 left,
@@ -112,6 +189,12 @@ impl Expression {
                 name,
                 value,
             } => visitor.visit_set(object, name, value),
+            Expression::SetIndex {
+                object,
+                bracket,
+                index,
+                value,
+            } => visitor.visit_set_index(object, bracket, index, value),
             Expression::Super { keyword, method } => visitor.visit_super(keyword, method),
             Expression::This { keyword } => visitor.visit_this(keyword),
             Expression::Unary { operator, right } => visitor.visit_unary(operator, right),
@@ -119,3 +202,41 @@ impl Expression {
         }
     }
 }
+
+impl Expression {
+    /// Position of the leftmost token of the expression, `None` for literals.
+    pub fn position(&self) -> Option<&TokenPosition> {
+        match self {
+            Expression::Assign { name, .. } => Some(&name.position),
+            Expression::Binary { left, operator, .. }
+            | Expression::Logical { left, operator, .. } => {
+                left.position().or(Some(&operator.position))
+            }
+            Expression::Compound {
+                target,
+                operator,
+                postfix,
+                ..
+            } => match operator.kind {
+                TokenType::PlusPlus | TokenType::MinusMinus if !postfix => Some(&operator.position),
+                _ => target.position().or(Some(&operator.position)),
+            },
+            Expression::Call { callee, paren, .. } => callee.position().or(Some(&paren.position)),
+            Expression::Get { object, name } => object.position().or(Some(&name.position)),
+            Expression::Grouping { expression } => expression.position(),
+            Expression::Index {
+                object, bracket, ..
+            } => object.position().or(Some(&bracket.position)),
+            Expression::Lambda { keyword, .. } => Some(&keyword.position),
+            Expression::Literal { .. } => None,
+            Expression::Set { object, name, .. } => object.position().or(Some(&name.position)),
+            Expression::SetIndex {
+                object, bracket, ..
+            } => object.position().or(Some(&bracket.position)),
+            Expression::Super { keyword, .. } => Some(&keyword.position),
+            Expression::This { keyword } => Some(&keyword.position),
+            Expression::Unary { operator, .. } => Some(&operator.position),
+            Expression::Variable { name } => Some(&name.position),
+        }
+    }
+}
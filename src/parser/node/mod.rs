@@ -2,4 +2,3 @@ pub mod expression;
 pub mod statement;
 
 pub use expression::{Expression, ExpressionVisitor};
-
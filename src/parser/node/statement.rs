@@ -1,4 +1,7 @@
-use crate::{parser::node::expression::Expression, token::Token};
+use crate::{
+    parser::node::expression::Expression,
+    token::{Token, TokenPosition},
+};
 
 #[derive(Debug, Clone)]
 pub enum Statement {
@@ -8,51 +11,108 @@ pub enum Statement {
     Class {
         name: Token,
         superclass: Option<Box<Expression>>,
-        methods: Vec<Statement>,
+        /// Traits listed after `with`, in order.
+        traits: Vec<Expression>,
+        body: ClassBody,
     },
     Expression {
         expression: Box<Expression>,
     },
     Function {
         name: Token,
-        params: Vec<Token>,
+        params: Vec<Parameter>,
         body: Box<Statement>,
     },
     If {
+        keyword: Token,
         condition: Box<Expression>,
         then_branch: Box<Statement>,
         else_branch: Option<Box<Statement>>,
     },
     Return {
+        keyword: Token,
         value: Option<Box<Expression>>,
     },
+    Trait {
+        name: Token,
+        methods: Vec<Statement>,
+    },
     Var {
         name: Token,
         initializer: Option<Box<Expression>>,
     },
     While {
+        keyword: Token,
         condition: Box<Expression>,
         body: Box<Statement>,
     },
 }
 
+/// Parameter of a function, `name`, `name = default` or `...name`.
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: Token,
+    /// Value used when a call leaves the parameter out, evaluated once when the function is
+    /// created.
+    pub default: Option<Expression>,
+    /// Collects the arguments left over into a list, only the last parameter can.
+    pub rest: bool,
+}
+
+/// Method a class declares without implementing it, which subclasses have to define.
+#[derive(Debug, Clone)]
+pub struct AbstractMethod {
+    pub name: Token,
+    pub params: Vec<Parameter>,
+}
+
+/// Members declared in a class body, grouped by kind.
+#[derive(Debug, Clone, Default)]
+pub struct ClassBody {
+    pub methods: Vec<Statement>,
+    /// `get` accessors, as function declarations without parameters.
+    pub getters: Vec<Statement>,
+    /// `set` accessors, as function declarations with the assigned value as parameter.
+    pub setters: Vec<Statement>,
+    /// `static` methods and fields, as function and variable declarations.
+    pub statics: Vec<Statement>,
+    /// Instance fields, as variable declarations evaluated for every new instance.
+    pub fields: Vec<Statement>,
+    pub abstracts: Vec<AbstractMethod>,
+}
+
+impl ClassBody {
+    /// Every member, in no particular order.
+    pub fn members(&self) -> impl Iterator<Item = &Statement> {
+        self.methods
+            .iter()
+            .chain(&self.getters)
+            .chain(&self.setters)
+            .chain(&self.statics)
+            .chain(&self.fields)
+    }
+}
+
 pub trait StatementVisitor<T> {
     fn visit_block(&mut self, statements: &mut [Statement]) -> T;
     fn visit_class(
         &mut self,
         name: &Token,
         superclass: &mut Option<Box<Expression>>,
-        methods: &mut [Statement],
+        traits: &mut [Expression],
+        body: &mut ClassBody,
     ) -> T;
     fn visit_expression(&mut self, expression: &mut Expression) -> T;
-    fn visit_function(&mut self, name: &Token, params: &[Token], body: &mut Statement) -> T;
+    fn visit_function(&mut self, name: &Token, params: &[Parameter], body: &mut Statement) -> T;
     fn visit_if(
         &mut self,
+        keyword: &Token,
         condition: &mut Expression,
         then_branch: &mut Statement,
         else_branch: &mut Option<Box<Statement>>,
     ) -> T;
     fn visit_return(&mut self, value: &mut Option<Box<Expression>>) -> T;
+    fn visit_trait(&mut self, name: &Token, methods: &mut [Statement]) -> T;
     fn visit_var(&mut self, name: &Token, initializer: &mut Option<Box<Expression>>) -> T;
     fn visit_while(&mut self, condition: &mut Expression, body: &mut Statement) -> T;
 }
@@ -64,20 +124,42 @@ impl Statement {
             Statement::Class {
                 name,
                 superclass,
-                methods,
-            } => visitor.visit_class(name, superclass, methods),
+                traits,
+                body,
+            } => visitor.visit_class(name, superclass, traits, body),
             Statement::Expression { expression } => visitor.visit_expression(expression),
             Statement::Function { name, params, body } => {
                 visitor.visit_function(name, params, body)
             }
             Statement::If {
+                keyword,
                 condition,
                 then_branch,
                 else_branch,
-            } => visitor.visit_if(condition, then_branch, else_branch),
-            Statement::Return { value } => visitor.visit_return(value),
+            } => visitor.visit_if(keyword, condition, then_branch, else_branch),
+            Statement::Return { value, .. } => visitor.visit_return(value),
+            Statement::Trait { name, methods } => visitor.visit_trait(name, methods),
             Statement::Var { name, initializer } => visitor.visit_var(name, initializer),
-            Statement::While { condition, body } => visitor.visit_while(condition, body),
+            Statement::While {
+                condition, body, ..
+            } => visitor.visit_while(condition, body),
+        }
+    }
+}
+
+impl Statement {
+    /// Position of the token that starts the statement, `None` for blocks and bare literals.
+    pub fn position(&self) -> Option<&TokenPosition> {
+        match self {
+            Statement::Block { .. } => None,
+            Statement::Class { name, .. } => Some(&name.position),
+            Statement::Expression { expression } => expression.position(),
+            Statement::Function { name, .. } => Some(&name.position),
+            Statement::If { keyword, .. } => Some(&keyword.position),
+            Statement::Return { keyword, .. } => Some(&keyword.position),
+            Statement::Trait { name, .. } => Some(&name.position),
+            Statement::Var { name, .. } => Some(&name.position),
+            Statement::While { keyword, .. } => Some(&keyword.position),
         }
     }
 }
@@ -0,0 +1,296 @@
+use crate::{
+    parser::node::{
+        Expression, ExpressionVisitor,
+        expression::NamedArgument,
+        statement::{ClassBody, Parameter, Statement, StatementVisitor},
+    },
+    token::{Token, kind::TokenType, value::TokenValue},
+};
+
+/// Renders the AST as parenthesized prefix notation, one top level statement per line.
+#[derive(Debug, Default)]
+pub struct AstPrinter;
+
+impl AstPrinter {
+    pub fn print(&mut self, statements: &mut [Statement]) -> String {
+        statements
+            .iter_mut()
+            .map(|stmt| stmt.accept(self))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn expression(&mut self, expression: &mut Expression) -> String {
+        expression.accept(self)
+    }
+
+    fn parenthesize(&mut self, name: &str, exprs: &mut [&mut Expression]) -> String {
+        let mut text = format!("({}", name);
+        for expr in exprs {
+            text.push(' ');
+            text.push_str(&expr.accept(self));
+        }
+        text.push(')');
+
+        text
+    }
+
+    fn statements(&mut self, statements: &mut [Statement]) -> String {
+        statements
+            .iter_mut()
+            .map(|stmt| format!(" {}", stmt.accept(self)))
+            .collect()
+    }
+
+    fn params(&mut self, params: &[Parameter]) -> String {
+        params
+            .iter()
+            .map(|param| match (&param.default, param.rest) {
+                (Some(default), _) => {
+                    format!("(= {} {})", param.name.lexeme, default.clone().accept(self))
+                }
+                (None, true) => format!("...{}", param.name.lexeme),
+                (None, false) => param.name.lexeme.to_owned(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+impl ExpressionVisitor<String> for AstPrinter {
+    fn visit_assign(&mut self, name: &Token, value: &mut Expression) -> String {
+        self.parenthesize(&format!("= {}", name.lexeme), &mut [value])
+    }
+
+    fn visit_binary(
+        &mut self,
+        left: &mut Expression,
+        operator: &Token,
+        right: &mut Expression,
+    ) -> String {
+        self.parenthesize(&operator.lexeme, &mut [left, right])
+    }
+
+    fn visit_call(
+        &mut self,
+        callee: &mut Expression,
+        _paren: &Token,
+        arguments: &mut [Expression],
+        named: &mut [NamedArgument],
+    ) -> String {
+        let mut exprs: Vec<&mut Expression> = vec![callee];
+        exprs.extend(arguments.iter_mut());
+        let mut text = self.parenthesize("call", &mut exprs);
+        text.pop();
+        for argument in named {
+            let name = format!("{}:", argument.name.lexeme);
+            text.push_str(&format!(
+                " {}",
+                self.parenthesize(&name, &mut [&mut argument.value])
+            ));
+        }
+        text.push(')');
+
+        text
+    }
+
+    fn visit_compound(
+        &mut self,
+        target: &mut Expression,
+        operator: &Token,
+        value: &mut Expression,
+        postfix: bool,
+    ) -> String {
+        match operator.kind {
+            TokenType::PlusPlus | TokenType::MinusMinus => {
+                let name = match postfix {
+                    true => format!("post{}", operator.lexeme),
+                    false => operator.lexeme.to_owned(),
+                };
+                self.parenthesize(&name, &mut [target])
+            }
+            _ => self.parenthesize(&operator.lexeme, &mut [target, value]),
+        }
+    }
+
+    fn visit_get(&mut self, object: &mut Expression, name: &Token) -> String {
+        self.parenthesize(&format!(". {}", name.lexeme), &mut [object])
+    }
+
+    fn visit_index(
+        &mut self,
+        object: &mut Expression,
+        _bracket: &Token,
+        index: &mut Expression,
+    ) -> String {
+        self.parenthesize("[]", &mut [object, index])
+    }
+
+    fn visit_grouping(&mut self, expression: &mut Expression) -> String {
+        self.parenthesize("group", &mut [expression])
+    }
+
+    fn visit_lambda(
+        &mut self,
+        _keyword: &Token,
+        params: &[Parameter],
+        body: &mut Statement,
+    ) -> String {
+        format!("(lambda ({}) {})", self.params(params), body.accept(self))
+    }
+
+    fn visit_literal(&mut self, value: &TokenValue) -> String {
+        match value {
+            TokenValue::String(val) => format!("{:?}", val),
+            _ => value.to_string(),
+        }
+    }
+
+    fn visit_logical(
+        &mut self,
+        left: &mut Expression,
+        operator: &Token,
+        right: &mut Expression,
+    ) -> String {
+        self.parenthesize(&operator.lexeme, &mut [left, right])
+    }
+
+    fn visit_set(
+        &mut self,
+        object: &mut Expression,
+        name: &Token,
+        value: &mut Expression,
+    ) -> String {
+        self.parenthesize(&format!(".= {}", name.lexeme), &mut [object, value])
+    }
+
+    fn visit_set_index(
+        &mut self,
+        object: &mut Expression,
+        _bracket: &Token,
+        index: &mut Expression,
+        value: &mut Expression,
+    ) -> String {
+        self.parenthesize("[]=", &mut [object, index, value])
+    }
+
+    fn visit_super(&mut self, _keyword: &Token, method: &Token) -> String {
+        format!("(super {})", method.lexeme)
+    }
+
+    fn visit_this(&mut self, _keyword: &Token) -> String {
+        "this".to_string()
+    }
+
+    fn visit_unary(&mut self, operator: &Token, right: &mut Expression) -> String {
+        self.parenthesize(&operator.lexeme, &mut [right])
+    }
+
+    fn visit_variable(&mut self, name: &Token) -> String {
+        name.lexeme.to_owned()
+    }
+}
+
+impl StatementVisitor<String> for AstPrinter {
+    fn visit_block(&mut self, statements: &mut [Statement]) -> String {
+        format!("(block{})", self.statements(statements))
+    }
+
+    fn visit_class(
+        &mut self,
+        name: &Token,
+        superclass: &mut Option<Box<Expression>>,
+        traits: &mut [Expression],
+        body: &mut ClassBody,
+    ) -> String {
+        let mut superclass = match superclass {
+            Some(superclass) => format!(" < {}", superclass.accept(self)),
+            None => String::new(),
+        };
+        if !traits.is_empty() {
+            let traits: Vec<String> = traits.iter_mut().map(|t| t.accept(self)).collect();
+            superclass.push_str(&format!(" with {}", traits.join(", ")));
+        }
+        let mut members = self.statements(&mut body.methods);
+        for (keyword, group) in [
+            ("field", &mut body.fields),
+            ("get", &mut body.getters),
+            ("set", &mut body.setters),
+            ("static", &mut body.statics),
+        ] {
+            if !group.is_empty() {
+                members.push_str(&format!(" ({}{})", keyword, self.statements(group)));
+            }
+        }
+        for method in &body.abstracts {
+            members.push_str(&format!(
+                " (abstract {} ({}))",
+                method.name.lexeme,
+                self.params(&method.params)
+            ));
+        }
+        format!("(class {}{}{})", name.lexeme, superclass, members)
+    }
+
+    fn visit_expression(&mut self, expression: &mut Expression) -> String {
+        self.parenthesize(";", &mut [expression])
+    }
+
+    fn visit_function(
+        &mut self,
+        name: &Token,
+        params: &[Parameter],
+        body: &mut Statement,
+    ) -> String {
+        format!(
+            "(fun {} ({}) {})",
+            name.lexeme,
+            self.params(params),
+            body.accept(self)
+        )
+    }
+
+    fn visit_if(
+        &mut self,
+        _keyword: &Token,
+        condition: &mut Expression,
+        then_branch: &mut Statement,
+        else_branch: &mut Option<Box<Statement>>,
+    ) -> String {
+        let condition = condition.accept(self);
+        let then_branch = then_branch.accept(self);
+        match else_branch {
+            Some(else_branch) => format!(
+                "(if {} {} {})",
+                condition,
+                then_branch,
+                else_branch.accept(self)
+            ),
+            None => format!("(if {} {})", condition, then_branch),
+        }
+    }
+
+    fn visit_return(&mut self, value: &mut Option<Box<Expression>>) -> String {
+        match value {
+            Some(value) => self.parenthesize("return", &mut [value]),
+            None => "(return)".to_string(),
+        }
+    }
+
+    fn visit_trait(&mut self, name: &Token, methods: &mut [Statement]) -> String {
+        format!("(trait {}{})", name.lexeme, self.statements(methods))
+    }
+
+    fn visit_var(&mut self, name: &Token, initializer: &mut Option<Box<Expression>>) -> String {
+        match initializer {
+            Some(initializer) => {
+                self.parenthesize(&format!("var {}", name.lexeme), &mut [initializer])
+            }
+            None => format!("(var {})", name.lexeme),
+        }
+    }
+
+    fn visit_while(&mut self, condition: &mut Expression, body: &mut Statement) -> String {
+        format!("(while {} {})", condition.accept(self), body.accept(self))
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+    interpreter::{Interpreter, hook::Hook},
+    parser::node::statement::Statement,
+    token::value::TokenValue,
+};
+
+const SCRIPT: &str = "<script>";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FunctionProfile {
+    pub calls: usize,
+    pub inclusive: Duration,
+    pub exclusive: Duration,
+}
+
+#[derive(Debug, Clone)]
+struct Activation {
+    name: String,
+    start: Instant,
+    children: Duration,
+}
+
+/// Measures the time spent in every call and counts how often each line runs.
+#[derive(Debug)]
+pub struct Profiler {
+    start: Instant,
+    stack: Vec<Activation>,
+    functions: HashMap<String, FunctionProfile>,
+    lines: HashMap<usize, usize>,
+    /// Exclusive time per call stack, keyed by the `;` separated frame names.
+    stacks: HashMap<String, Duration>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self {
+            start: Instant::now(),
+            stack: Vec::new(),
+            functions: HashMap::new(),
+            lines: HashMap::new(),
+            stacks: HashMap::new(),
+        }
+    }
+}
+
+impl Profiler {
+    /// Time spent running the script outside of any function.
+    fn script_time(&self) -> Duration {
+        let children: Duration = self
+            .stacks
+            .iter()
+            .filter(|(stack, _)| stack.as_str() != SCRIPT)
+            .map(|(_, time)| *time)
+            .sum();
+        self.start.elapsed().saturating_sub(children)
+    }
+
+    fn stack_key(&self) -> String {
+        std::iter::once(SCRIPT)
+            .chain(self.stack.iter().map(|activation| activation.name.as_str()))
+            .collect::<Vec<_>>()
+            .join(";")
+    }
+
+    pub fn report(&self, out: &mut impl Write) -> Result<()> {
+        let mut functions: Vec<(&String, &FunctionProfile)> = self.functions.iter().collect();
+        functions.sort_by(|a, b| b.1.inclusive.cmp(&a.1.inclusive).then(a.0.cmp(b.0)));
+
+        writeln!(
+            out,
+            "{:<24} {:>10} {:>14} {:>14}",
+            "Function", "Calls", "Inclusive ms", "Exclusive ms"
+        )?;
+        for (name, profile) in functions {
+            writeln!(
+                out,
+                "{:<24} {:>10} {:>14.3} {:>14.3}",
+                name,
+                profile.calls,
+                profile.inclusive.as_secs_f64() * 1000.0,
+                profile.exclusive.as_secs_f64() * 1000.0
+            )?;
+        }
+
+        let mut lines: Vec<(&usize, &usize)> = self.lines.iter().collect();
+        lines.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+        writeln!(out)?;
+        writeln!(out, "{:<24} {:>10}", "Line", "Hits")?;
+        for (line, hits) in lines {
+            writeln!(out, "{:<24} {:>10}", line, hits)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes one `frame;frame;frame microseconds` line per stack, the format flamegraph
+    /// tools call collapsed or folded stacks.
+    pub fn write_collapsed(&self, out: &mut impl Write) -> Result<()> {
+        let mut stacks: Vec<(String, Duration)> = self
+            .stacks
+            .iter()
+            .map(|(stack, time)| (stack.to_owned(), *time))
+            .collect();
+        stacks.push((SCRIPT.to_string(), self.script_time()));
+        stacks.sort();
+
+        for (stack, time) in stacks {
+            let micros = time.as_micros();
+            if micros > 0 {
+                writeln!(out, "{} {}", stack, micros)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Hook for Profiler {
+    fn on_statement(
+        &mut self,
+        _interpreter: &mut Interpreter,
+        statement: &Statement,
+    ) -> Result<()> {
+        if let Some(position) = statement.position() {
+            *self.lines.entry(position.row).or_default() += 1;
+        }
+
+        Ok(())
+    }
+
+    fn on_call(&mut self, interpreter: &mut Interpreter, _arguments: &[TokenValue]) -> Result<()> {
+        // Calls that failed never reported their return, drop what they left behind.
+        self.stack
+            .truncate(interpreter.frames().len().saturating_sub(1));
+
+        if let Some(frame) = interpreter.frames().last() {
+            self.stack.push(Activation {
+                name: interpreter.qualified_name(frame),
+                start: Instant::now(),
+                children: Duration::ZERO,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn on_return(&mut self, _interpreter: &mut Interpreter, _value: &TokenValue) -> Result<()> {
+        let key = self.stack_key();
+        let Some(activation) = self.stack.pop() else {
+            return Ok(());
+        };
+
+        let inclusive = activation.start.elapsed();
+        let exclusive = inclusive.saturating_sub(activation.children);
+        if let Some(parent) = self.stack.last_mut() {
+            parent.children += inclusive;
+        }
+        *self.stacks.entry(key).or_default() += exclusive;
+
+        let recursive = self
+            .stack
+            .iter()
+            .any(|caller| caller.name == activation.name);
+        let profile = self.functions.entry(activation.name).or_default();
+        profile.calls += 1;
+        profile.exclusive += exclusive;
+        // Recursive calls are already covered by the outermost call of the function.
+        if !recursive {
+            profile.inclusive += inclusive;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+    use crate::interpreter::testing::capture;
+
+    #[test]
+    fn qualifies_methods_and_lambdas() {
+        let profiler = Rc::new(RefCell::new(Profiler::default()));
+        let mut interpreter = Interpreter::default();
+        interpreter.attach(Box::new(profiler.clone()));
+
+        capture(
+            &mut interpreter,
+            "class Cat { init() {} speak() { return (() => \"meow\")(); } }
+class Dog { init() {} speak() { return \"woof\"; } }
+Cat().speak();
+Dog().speak();
+Dog().speak();
+var twice = (f) => f() + f();
+twice(() => 1);
+",
+        )
+        .unwrap();
+
+        let profiler = profiler.borrow();
+        let mut names: Vec<(&str, usize)> = profiler
+            .functions
+            .iter()
+            .map(|(name, profile)| (name.as_str(), profile.calls))
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                ("Cat.init", 1),
+                ("Cat.lambda@1", 1),
+                ("Cat.speak", 1),
+                ("Dog.init", 2),
+                ("Dog.speak", 2),
+                ("lambda@6", 1),
+                ("lambda@7", 2),
+            ]
+        );
+
+        assert!(
+            profiler
+                .stacks
+                .contains_key("<script>;Cat.speak;Cat.lambda@1")
+        );
+        assert!(profiler.stacks.contains_key("<script>;lambda@6;lambda@7"));
+    }
+
+    #[test]
+    fn counts_calls_and_lines() {
+        let profiler = Rc::new(RefCell::new(Profiler::default()));
+        let mut interpreter = Interpreter::default();
+        interpreter.attach(Box::new(profiler.clone()));
+
+        capture(
+            &mut interpreter,
+            "fun fib(n) {
+  if (n < 2) return n;
+  return fib(n - 1) + fib(n - 2);
+}
+print(fib(5));
+",
+        )
+        .unwrap();
+
+        let profiler = profiler.borrow();
+        assert_eq!(profiler.functions["fib"].calls, 15);
+        assert_eq!(profiler.functions["print"].calls, 1);
+        assert_eq!(profiler.lines[&2], 15 + 8);
+        assert_eq!(profiler.lines[&3], 7);
+
+        let mut collapsed = Vec::new();
+        profiler.write_collapsed(&mut collapsed).unwrap();
+        let collapsed = String::from_utf8(collapsed).unwrap();
+        assert!(collapsed.lines().all(|line| line.starts_with("<script>")));
+    }
+}
@@ -0,0 +1,114 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    Exit,
+    Env,
+    Load(PathBuf),
+    Save(PathBuf),
+    Reset,
+    Ast(String),
+    Tokens(String),
+    Time(String),
+}
+
+pub const HELP: &str = "\
+.help              Show this message
+.exit              Leave the REPL
+.env               List the global variables and their values
+.load <file.rot>   Run a script inside the current session
+.save <file.rot>   Write the successful inputs of this session to a file
+.reset             Start over with a fresh interpreter
+.ast <source>      Show the parse tree of the source
+.tokens <source>   Show the token stream of the source
+.time <source>     Run the source and show how long it took";
+
+impl Command {
+    /// Parses a REPL meta-command, returning `None` when the line is regular source code.
+    pub fn parse(line: &str) -> Option<Result<Self>> {
+        let line = line.trim();
+        if !line.starts_with('.') {
+            return None;
+        }
+
+        let (name, argument) = match line.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (line, ""),
+        };
+
+        let command = match name {
+            ".help" => Ok(Command::Help),
+            ".exit" => Ok(Command::Exit),
+            ".env" => Ok(Command::Env),
+            ".reset" => Ok(Command::Reset),
+            ".load" => Self::required(name, argument).map(|arg| Command::Load(arg.into())),
+            ".save" => Self::required(name, argument).map(|arg| Command::Save(arg.into())),
+            ".ast" => Self::required(name, argument).map(|arg| Command::Ast(arg.to_owned())),
+            ".tokens" => Self::required(name, argument).map(|arg| Command::Tokens(arg.to_owned())),
+            ".time" => Self::required(name, argument).map(|arg| Command::Time(arg.to_owned())),
+            _ => Err(anyhow::anyhow!(
+                "Unknown command '{}', type .help for the list of commands",
+                name
+            )),
+        };
+
+        Some(command)
+    }
+
+    fn required<'a>(name: &str, argument: &'a str) -> Result<&'a str> {
+        if argument.is_empty() {
+            anyhow::bail!("Command '{}' expects an argument", name);
+        }
+
+        Ok(argument)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn source_is_not_a_command() {
+        assert!(Command::parse("print(1);").is_none());
+        assert!(Command::parse("1.5;").is_none());
+    }
+
+    #[test]
+    fn commands_without_arguments() {
+        assert_eq!(Command::parse(".help").unwrap().unwrap(), Command::Help);
+        assert_eq!(Command::parse("  .exit\n").unwrap().unwrap(), Command::Exit);
+        assert_eq!(Command::parse(".env").unwrap().unwrap(), Command::Env);
+        assert_eq!(Command::parse(".reset").unwrap().unwrap(), Command::Reset);
+    }
+
+    #[test]
+    fn commands_with_arguments() {
+        assert_eq!(
+            Command::parse(".load lib/math.rot").unwrap().unwrap(),
+            Command::Load("lib/math.rot".into())
+        );
+        assert_eq!(
+            Command::parse(".ast 1 + 2 * 3;").unwrap().unwrap(),
+            Command::Ast("1 + 2 * 3;".to_string())
+        );
+        assert_eq!(
+            Command::parse(".time   fib(20);").unwrap().unwrap(),
+            Command::Time("fib(20);".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_argument() {
+        assert!(Command::parse(".save").unwrap().is_err());
+        assert!(Command::parse(".tokens   ").unwrap().is_err());
+    }
+
+    #[test]
+    fn unknown_command() {
+        assert!(Command::parse(".quit").unwrap().is_err());
+    }
+}
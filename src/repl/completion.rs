@@ -0,0 +1,206 @@
+use std::{cell::RefCell, rc::Rc};
+
+use rustyline::{
+    Context, Helper, completion::Completer, highlight::Highlighter, hint::Hinter,
+    validate::Validator,
+};
+
+use crate::{interpreter::Interpreter, lexer::keywords, token::value::TokenValue};
+
+/// Completes keywords, names defined in the interpreter and, after a `.`, the members of
+/// the value a plain variable chain such as `a.b.` resolves to.
+pub struct Completion {
+    interpreter: Rc<RefCell<Interpreter>>,
+}
+
+impl Completion {
+    pub fn new(interpreter: Rc<RefCell<Interpreter>>) -> Self {
+        Self { interpreter }
+    }
+
+    fn candidates(&self, line: &str, start: usize) -> Vec<String> {
+        let before = &line[..start];
+        match before.strip_suffix('.') {
+            Some(chain) => {
+                let chain_start = Self::word_start(chain, Self::is_chain_char);
+                self.members(&chain[chain_start..])
+            }
+            None => {
+                let mut names: Vec<String> = keywords::create_keywords()
+                    .into_keys()
+                    .map(|keyword| keyword.to_string())
+                    .collect();
+                names.extend(self.interpreter.borrow().names());
+                names
+            }
+        }
+    }
+
+    /// Resolves the chain through variable lookups and field reads only, so completing never
+    /// runs user code.
+    fn members(&self, chain: &str) -> Vec<String> {
+        let mut segments = chain.split('.');
+        let first = segments.next().unwrap_or_default();
+        if first.is_empty() || first.starts_with(|c: char| c.is_ascii_digit()) {
+            return Vec::new();
+        }
+
+        let interpreter = self.interpreter.borrow();
+        let mut value = interpreter.lookup(first);
+        for segment in segments {
+            value = match value {
+                Some(TokenValue::Instance(instance)) => {
+                    interpreter.heap().instance(instance).fields.lookup(segment)
+                }
+                Some(TokenValue::Class(class)) => interpreter.heap().find_static(class, segment),
+                _ => None,
+            };
+        }
+
+        match value {
+            Some(TokenValue::Instance(instance)) => interpreter.heap().member_names(instance),
+            // Only static members are read through a class, private ones stay hidden.
+            Some(TokenValue::Class(class)) => {
+                let mut names = interpreter.heap().static_names(class);
+                names.retain(|name| !name.starts_with('#'));
+                names
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Byte offset just past the last character of `text` that isn't part of the word.
+    fn word_start(text: &str, is_part: fn(char) -> bool) -> usize {
+        text.char_indices()
+            .rev()
+            .find(|(_, c)| !is_part(*c))
+            .map(|(index, c)| index + c.len_utf8())
+            .unwrap_or(0)
+    }
+
+    fn is_word_char(c: char) -> bool {
+        c.is_ascii_alphanumeric() || c == '_'
+    }
+
+    fn is_chain_char(c: char) -> bool {
+        Self::is_word_char(c) || c == '.'
+    }
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = Self::word_start(&line[..pos], Self::is_word_char);
+        let word = &line[start..pos];
+
+        let mut candidates: Vec<String> = self
+            .candidates(line, start)
+            .into_iter()
+            .filter(|candidate| candidate.starts_with(word))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+#[cfg(test)]
+mod tests {
+    use rustyline::history::DefaultHistory;
+
+    use super::*;
+
+    fn complete(completion: &Completion, line: &str) -> (usize, Vec<String>) {
+        let history = DefaultHistory::new();
+        completion
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap()
+    }
+
+    fn completion(source: &str) -> Completion {
+        let interpreter = Rc::new(RefCell::new(Interpreter::default()));
+        crate::run(&mut interpreter.borrow_mut(), source.to_string()).unwrap();
+        Completion::new(interpreter)
+    }
+
+    #[test]
+    fn keywords_and_names() {
+        let completion = completion("var counter = 1; var color = 2;");
+        assert_eq!(
+            complete(&completion, "print(co"),
+            (6, vec!["color".to_string(), "counter".to_string()])
+        );
+        assert_eq!(complete(&completion, "whi"), (0, vec!["while".to_string()]));
+    }
+
+    #[test]
+    fn instance_members() {
+        let completion = completion(
+            "class Animal { speak() {} static species() {} }
+            class Dog < Animal { fetch() {} static #secret = 1; static sound = \"woof\"; }
+            var dog = Dog();
+            dog.name = \"Rex\";
+            dog.owner = Dog();
+            dog.owner.nick = \"Bob\";",
+        );
+        assert_eq!(
+            complete(&completion, "dog."),
+            (
+                4,
+                vec![
+                    "fetch".to_string(),
+                    "name".to_string(),
+                    "owner".to_string(),
+                    "speak".to_string()
+                ]
+            )
+        );
+        assert_eq!(
+            complete(&completion, "print(dog.owner.n"),
+            (16, vec!["nick".to_string()])
+        );
+        assert_eq!(
+            complete(&completion, "Dog.s"),
+            (4, vec!["sound".to_string(), "species".to_string()])
+        );
+        assert_eq!(complete(&completion, "Dog.f"), (4, Vec::new()));
+        assert_eq!(
+            complete(&completion, "Dog."),
+            (4, vec!["sound".to_string(), "species".to_string()])
+        );
+    }
+
+    #[test]
+    fn calls_are_not_evaluated() {
+        let completion = completion("fun make() { print(\"side effect\"); }");
+        assert_eq!(complete(&completion, "make()."), (7, Vec::new()));
+        assert_eq!(complete(&completion, "1."), (2, Vec::new()));
+    }
+
+    #[test]
+    fn non_ascii_separators() {
+        let completion = completion("var counter = 1;");
+        assert_eq!(
+            complete(&completion, "éco"),
+            (2, vec!["counter".to_string()])
+        );
+        assert_eq!(complete(&completion, "é.co"), (3, Vec::new()));
+    }
+}
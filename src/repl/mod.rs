@@ -0,0 +1,188 @@
+use std::{cell::RefCell, fs, path::Path, rc::Rc, time::Instant};
+
+use anyhow::Result;
+use log::error;
+use rustyline::{Editor, error::ReadlineError, history::DefaultHistory};
+
+use crate::{
+    interpreter::Interpreter,
+    lexer,
+    parser::{self, printer::AstPrinter},
+    repl::{
+        command::{Command, HELP},
+        completion::Completion,
+    },
+    token::value::TokenValue,
+};
+
+mod command;
+mod completion;
+
+#[derive(Debug, Default)]
+pub struct Repl {
+    interpreter: Rc<RefCell<Interpreter>>,
+    history: Vec<String>,
+}
+
+impl Repl {
+    pub fn run(&mut self) {
+        println!("Welcome to rotten v{}", env!("CARGO_PKG_VERSION"));
+        println!("Type .help for the list of commands");
+
+        let mut editor: Editor<Completion, DefaultHistory> = match Editor::new() {
+            Ok(editor) => editor,
+            Err(e) => {
+                error!("Couldn't start the line editor: {}", e);
+                return;
+            }
+        };
+        editor.set_helper(Some(Completion::new(self.interpreter.clone())));
+
+        loop {
+            let line = match editor.readline("> ") {
+                Ok(line) => line,
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(e) => {
+                    error!("Couldn't read input: {}", e);
+                    break;
+                }
+            };
+            if !line.trim().is_empty() {
+                let _ = editor.add_history_entry(line.as_str());
+            }
+
+            match Command::parse(&line) {
+                Some(Ok(Command::Exit)) => break,
+                Some(Ok(command)) => {
+                    if let Err(e) = self.execute(command) {
+                        println!("{}", e);
+                    }
+                }
+                Some(Err(e)) => println!("{}", e),
+                None => self.eval(line),
+            }
+        }
+    }
+
+    fn eval(&mut self, line: String) {
+        if line.trim().is_empty() {
+            return;
+        }
+
+        match self.interpret(line.clone()) {
+            Ok(val) => {
+                self.remember(line);
+                if let Some(val) = val {
+                    self.show(&val);
+                }
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    fn show(&self, value: &TokenValue) {
+        println!("-> {}", self.interpreter.borrow().stringify(value));
+    }
+
+    fn execute(&mut self, command: Command) -> Result<()> {
+        match command {
+            Command::Help => println!("{}", HELP),
+            Command::Exit => {}
+            Command::Env => {
+                let interpreter = self.interpreter.borrow();
+                for (name, value) in interpreter.globals() {
+                    println!("{} = {}", name, interpreter.stringify(&value));
+                }
+            }
+            Command::Load(path) => {
+                let source = Self::read(&path)?;
+                if let Some(val) = self.interpret(source.clone())? {
+                    self.show(&val);
+                }
+                self.remember(source);
+            }
+            Command::Save(path) => {
+                fs::write(&path, self.history.concat())
+                    .map_err(|e| anyhow::anyhow!("Couldn't write {}: {}", path.display(), e))?;
+                println!("Saved {} inputs to {}", self.history.len(), path.display());
+            }
+            Command::Reset => {
+                *self.interpreter.borrow_mut() = Interpreter::default();
+                self.history.clear();
+                println!("Session reset");
+            }
+            Command::Ast(source) => {
+                let tokens = lexer::run(source)?;
+                let mut stmts = parser::Parser::new(tokens).parse();
+                println!("{}", AstPrinter.print(&mut stmts));
+            }
+            Command::Tokens(source) => {
+                for token in lexer::run(source)? {
+                    println!(
+                        "[{}:{}] {:?} {:?}",
+                        token.position.row, token.position.column, token.kind, token.lexeme
+                    );
+                }
+            }
+            Command::Time(source) => {
+                let start = Instant::now();
+                let val = self.interpret(source.clone())?;
+                let elapsed = start.elapsed();
+
+                self.remember(source);
+                if let Some(val) = val {
+                    self.show(&val);
+                }
+                println!("Elapsed: {:?}", elapsed);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs `source` unless part of it doesn't parse, so only inputs that ran are remembered.
+    fn interpret(&mut self, source: String) -> Result<Option<TokenValue>> {
+        let tokens = lexer::run(source)?;
+        let (mut stmts, errors) = parser::Parser::new(tokens).parse_with_errors();
+        if !errors.is_empty() {
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            anyhow::bail!("{}", errors.join("\n"));
+        }
+
+        self.interpreter.borrow_mut().interpret(&mut stmts)
+    }
+
+    fn remember(&mut self, mut source: String) {
+        if !source.ends_with('\n') {
+            source.push('\n');
+        }
+        self.history.push(source);
+    }
+
+    fn read(path: &Path) -> Result<String> {
+        fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Couldn't read {}: {}", path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_only_inputs_that_ran() {
+        let mut repl = Repl::default();
+        repl.eval("var x = ;".to_string());
+        repl.eval("var y = 1;".to_string());
+        repl.eval("y = missing;".to_string());
+        repl.eval("print(y);".to_string());
+
+        let path = std::env::temp_dir().join(format!("rotten-save-{}.rot", std::process::id()));
+        repl.execute(Command::Save(path.clone())).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(saved, "var y = 1;\nprint(y);\n");
+    }
+}
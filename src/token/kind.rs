@@ -7,15 +7,20 @@ pub enum TokenType {
     RightParen,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Colon,
     Comma,
     Dot,
     Minus,
+    Percent,
     Plus,
     Semicolon,
     Slash,
     Star,
 
     // One or two character tokens.
+    Arrow,
     Bang,
     BangEqual,
     Equal,
@@ -24,6 +29,14 @@ pub enum TokenType {
     GreaterEqual,
     Less,
     LessEqual,
+    Ellipsis,
+    MinusEqual,
+    MinusMinus,
+    PercentEqual,
+    PlusEqual,
+    PlusPlus,
+    SlashEqual,
+    StarEqual,
 
     // Literals.
     Identifier,
@@ -31,6 +44,7 @@ pub enum TokenType {
     Number,
 
     // Keywords.
+    Abstract,
     And,
     Class,
     Else,
@@ -41,11 +55,14 @@ pub enum TokenType {
     Nil,
     Or,
     Return,
+    Static,
     Super,
     This,
+    Trait,
     True,
     Var,
     While,
+    With,
 
     EndOfFile,
 }
@@ -57,13 +74,18 @@ impl Display for TokenType {
             TokenType::RightParen => ")",
             TokenType::LeftBrace => "{",
             TokenType::RightBrace => "}",
+            TokenType::LeftBracket => "[",
+            TokenType::RightBracket => "]",
+            TokenType::Colon => ":",
             TokenType::Comma => ",",
             TokenType::Dot => ".",
             TokenType::Minus => "-",
+            TokenType::Percent => "%",
             TokenType::Plus => "+",
             TokenType::Semicolon => ";",
             TokenType::Slash => "/",
             TokenType::Star => "*",
+            TokenType::Arrow => "=>",
             TokenType::Bang => "!",
             TokenType::BangEqual => "!=",
             TokenType::Equal => "=",
@@ -72,9 +94,18 @@ impl Display for TokenType {
             TokenType::GreaterEqual => ">=",
             TokenType::Less => "<",
             TokenType::LessEqual => "<=",
+            TokenType::Ellipsis => "...",
+            TokenType::MinusEqual => "-=",
+            TokenType::MinusMinus => "--",
+            TokenType::PercentEqual => "%=",
+            TokenType::PlusEqual => "+=",
+            TokenType::PlusPlus => "++",
+            TokenType::SlashEqual => "/=",
+            TokenType::StarEqual => "*=",
             TokenType::Identifier => "identifier",
             TokenType::String => "string",
             TokenType::Number => "number",
+            TokenType::Abstract => "abstract",
             TokenType::And => "and",
             TokenType::Class => "class",
             TokenType::Else => "else",
@@ -85,11 +116,14 @@ impl Display for TokenType {
             TokenType::Nil => "nil",
             TokenType::Or => "or",
             TokenType::Return => "return",
+            TokenType::Static => "static",
             TokenType::Super => "super",
             TokenType::This => "this",
+            TokenType::Trait => "trait",
             TokenType::True => "true",
             TokenType::Var => "var",
             TokenType::While => "while",
+            TokenType::With => "with",
             TokenType::EndOfFile => "EOF",
         };
         write!(f, "{}", s)
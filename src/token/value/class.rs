@@ -1,40 +1,168 @@
-use std::{collections::HashMap, sync::Arc};
+use std::collections::HashMap;
 
-use crate::token::value::function::Function;
+use crate::{
+    memory::{environment::Environment, heap::Handle},
+    parser::node::expression::Expression,
+    token::value::function::Function,
+};
+
+/// Field declared in a class body, initialized on every new instance.
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub initializer: Option<Expression>,
+}
 
 #[derive(Debug, Clone)]
 pub struct Class {
     pub name: String,
-    superclass: Option<Arc<Class>>,
+    pub superclass: Option<Handle>,
+    /// Traits composed into the class, whose methods were copied into it.
+    pub traits: Vec<Handle>,
     methods: HashMap<String, Function>,
+    getters: HashMap<String, Function>,
+    setters: HashMap<String, Function>,
+    fields: Vec<Field>,
+    /// Methods declared `abstract`, which subclasses have to define.
+    pub abstracts: Vec<String>,
+    /// Static fields and methods, read through the class itself.
+    pub statics: Environment,
+    /// Variables captured where the class was declared, which its methods and field
+    /// initializers close over.
+    pub closure: Option<Handle>,
 }
 
 impl Class {
-    pub fn new(
-        name: String,
-        superclass: Option<Arc<Class>>,
-        methods: HashMap<String, Function>,
-    ) -> Self {
+    pub fn new(name: String, superclass: Option<Handle>) -> Self {
         Self {
             name,
             superclass,
-            methods,
+            traits: Vec::new(),
+            methods: HashMap::new(),
+            getters: HashMap::new(),
+            setters: HashMap::new(),
+            fields: Vec::new(),
+            abstracts: Vec::new(),
+            statics: Environment::default(),
+            closure: None,
         }
     }
 
-    pub fn get(&self, identifier: String) -> Option<Function> {
-        if let Some(method) = self.methods.get(&identifier) {
-            return Some(method.to_owned());
-        }
+    pub fn define_method(&mut self, method: Function) {
+        self.methods.insert(method.data.name.to_owned(), method);
+    }
 
-        self.get_from_super(identifier)
+    pub fn define_getter(&mut self, getter: Function) {
+        self.getters.insert(getter.data.name.to_owned(), getter);
     }
 
-    pub fn get_from_super(&self, identifier: String) -> Option<Function> {
-        if let Some(superclass) = &self.superclass {
-            return superclass.get(identifier);
-        }
+    pub fn define_setter(&mut self, setter: Function) {
+        self.setters.insert(setter.data.name.to_owned(), setter);
+    }
+
+    pub fn declare_field(&mut self, field: Field) {
+        self.fields.push(field);
+    }
+
+    /// Method declared by this class itself, the heap resolves inherited ones.
+    pub fn method(&self, name: &str) -> Option<&Function> {
+        self.methods.get(name)
+    }
+
+    /// Accessor run when the property `name` is read.
+    pub fn getter(&self, name: &str) -> Option<&Function> {
+        self.getters.get(name)
+    }
+
+    /// Accessor run when the property `name` is assigned.
+    pub fn setter(&self, name: &str) -> Option<&Function> {
+        self.setters.get(name)
+    }
+
+    /// Fields declared by this class itself, in declaration order.
+    pub fn fields(&self) -> &[Field] {
+        &self.fields
+    }
+
+    /// Whether the body of this class declares a member named `name`.
+    pub fn declares(&self, name: &str) -> bool {
+        self.methods.contains_key(name)
+            || self.getters.contains_key(name)
+            || self.setters.contains_key(name)
+            || self.fields.iter().any(|field| field.name == name)
+            || self.statics.lookup(name).is_some()
+    }
+
+    /// Methods declared by this class or composed into it from traits, without accessors.
+    pub fn methods(&self) -> impl Iterator<Item = &Function> {
+        self.methods.values()
+    }
+
+    /// Methods and accessors declared by this class.
+    pub fn functions(&self) -> impl Iterator<Item = &Function> {
+        self.methods
+            .values()
+            .chain(self.getters.values())
+            .chain(self.setters.values())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::testing::{fail, run};
+
+    #[test]
+    fn reads_static_members_through_the_class() {
+        let output = run("class Point {
+  static ORIGIN_X = 0;
+  static count;
+  init(x, y) { this.x = x; this.y = y; }
+  static origin() { return Point(Point.ORIGIN_X, 0); }
+}
+class Config {
+  static DEFAULT_PORT = 8080;
+  static describe() { return \"port \" + Config.DEFAULT_PORT; }
+}
+class Local < Config {
+  static HOST = \"localhost\";
+}
+print(Point.origin().y);
+print(Point.count);
+Point.count = 2;
+print(Point.count);
+print(Local.DEFAULT_PORT);
+print(Local.describe());
+Local.DEFAULT_PORT = 3000;
+print(Local.DEFAULT_PORT);
+print(Config.DEFAULT_PORT);");
+        assert_eq!(output, "0\nnil\n2\n8080\nport 8080\n3000\n8080\n");
+    }
+
+    #[test]
+    fn runs_property_accessors() {
+        let output = run("class Rect {
+  init(w, h) { this.w = w; this.h = h; }
+  get area { return this.w * this.h; }
+  set width(value) {
+    if (value < 0) value = 0;
+    this.w = value;
+  }
+  get() { return \"method\"; }
+}
+class Square < Rect {
+  init(side) { super.init(side, side); }
+}
+var r = Rect(2, 3);
+print(r.area);
+r.width = 5;
+print(r.area);
+r.width = -1;
+print(r.w);
+print(r.get());
+print(Square(4).area);");
+        assert_eq!(output, "6\n15\n0\nmethod\n16\n");
 
-        None
+        let error = fail("class Rect { get area { return 1; } }\nRect().area = 2;");
+        assert!(error.starts_with("[2:8] Interpreter Error: Property 'area' has no setter"));
     }
 }
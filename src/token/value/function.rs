@@ -1,9 +1,8 @@
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
 use crate::{
-    interpreter::Interpreter,
-    parser::node::statement::Statement,
-    token::value::{TokenValue, instance::Instance},
+    interpreter::Interpreter, memory::heap::Handle, parser::node::statement::Statement,
+    token::value::TokenValue,
 };
 
 pub type NativeFn =
@@ -17,7 +16,87 @@ pub struct Function {
 
 #[derive(Debug, Clone)]
 pub struct FunctionData {
-    pub body: Option<Vec<Statement>>,
+    pub name: String,
+    /// Line the function was declared on, natives have none.
+    pub line: Option<usize>,
+    /// Shared by every copy of the function, which gives declarations an identity.
+    pub body: Option<Arc<Vec<Statement>>>,
     pub params: Vec<String>,
-    pub this: Option<Arc<RwLock<Instance>>>,
+    /// Values of the last parameters before the rest parameter, for calls leaving them out.
+    pub defaults: Vec<TokenValue>,
+    /// The last parameter collects the arguments left over into a list.
+    pub rest: bool,
+    pub this: Option<Handle>,
+    /// Class declaring the method, whose superclass `super` looks methods up in.
+    pub class: Option<Handle>,
+    /// Initializers always return the instance they are bound to.
+    pub initializer: bool,
+    /// Variables captured from the scopes the function was created in, which its calls
+    /// read and update.
+    pub closure: Option<Handle>,
+}
+
+impl FunctionData {
+    /// Fewest and most arguments a call can pass, there is no most with a rest parameter.
+    pub fn arity(&self) -> (usize, Option<usize>) {
+        let fixed = self.params.len() - usize::from(self.rest);
+        (fixed - self.defaults.len(), (!self.rest).then_some(fixed))
+    }
+}
+
+impl PartialEq for Function {
+    /// Functions are equal when they come from the same declaration, or are the same native
+    /// function, and are bound to the same instance.
+    fn eq(&self, other: &Self) -> bool {
+        let same_declaration = match (&self.data.body, &other.data.body) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => self.data.name == other.data.name,
+            _ => false,
+        };
+
+        same_declaration && self.data.this == other.data.this
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::testing::{fail, run};
+
+    #[test]
+    fn binds_default_rest_and_named_arguments() {
+        let output = run("fun greet(name, greeting = \"Hello\", mark = \"!\") {
+  return greeting + \", \" + name + mark;
+}
+print(greet(\"Ada\"));
+print(greet(\"Ada\", mark: \"?\"));
+print(greet(greeting: \"Hi\", name: \"Bob\"));
+fun count(first, ...rest) { return len(rest); }
+print(count(1));
+print(count(1, 2, 3));
+fun tail(first, ...rest) { return rest; }
+print(tail(1, 2, 3));
+class Point { init(x, y = 0) { this.x = x; this.y = y; } }
+print(Point(y: 2, x: 1));
+print(greet);
+print(arity(greet));
+print(count);
+print(arity(count));
+");
+        assert_eq!(
+            output,
+            "Hello, Ada!\nHello, Ada?\nHi, Bob!\n0\n2\n[2, 3]\nPoint { x: 1, y: 2 }\n\
+<fn greet/1..3>\n1\n<fn count/1..>\n1\n"
+        );
+
+        assert!(
+            fail("fun f(a, b = 1) {}\nf();")
+                .contains("[2:3] Interpreter Error: Expected 1 to 2 arguments but got 0")
+        );
+        assert!(
+            fail("fun f(a, ...b) {}\nf();").contains("Expected at least 1 arguments but got 0")
+        );
+        assert!(fail("fun f(a) {}\nf(b: 1);").contains("No parameter named 'b'"));
+        assert!(fail("fun f(a) {}\nf(1, a: 2);").contains("Argument 'a' is passed more than once"));
+        assert!(fail("fun f(a, b) {}\nf(b: 1);").contains("Missing argument 'a'"));
+    }
 }
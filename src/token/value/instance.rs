@@ -1,60 +1,119 @@
-use std::sync::{Arc, RwLock};
+use std::collections::HashMap;
 
 use crate::{
-    interpreter::Interpreter,
-    memory::environment::Environment,
-    token::{
-        Token,
-        value::{Class, TokenValue},
-    },
+    memory::{environment::Environment, heap::Handle},
+    token::value::TokenValue,
 };
 
 #[derive(Debug, Clone)]
 pub struct Instance {
-    pub class: Arc<Class>,
+    pub class: Handle,
     pub fields: Environment,
-    this: Option<Arc<RwLock<Instance>>>,
+    /// Private fields by the class declaring them, so a subclass declaring the same `#name`
+    /// doesn't overwrite the field of its superclass.
+    pub privates: HashMap<Handle, Environment>,
 }
 
 impl Instance {
-    pub fn new(
-        class: Arc<Class>,
-        interpreter: &mut Interpreter,
-        arguments: Vec<TokenValue>,
-    ) -> anyhow::Result<Arc<RwLock<Self>>> {
-        let this = Self {
-            class: class.clone(),
+    pub fn new(class: Handle) -> Self {
+        Self {
+            class,
             fields: Default::default(),
-            this: None,
-        };
+            privates: HashMap::new(),
+        }
+    }
 
-        let res = Arc::new(RwLock::new(this));
-        res.write().unwrap().this = Some(res.clone());
+    /// Field named `name`, a private one is looked up among those `class` declared.
+    pub fn field(&self, class: Handle, name: &str) -> Option<TokenValue> {
+        match name.starts_with('#') {
+            true => self.privates.get(&class)?.lookup(name),
+            false => self.fields.lookup(name),
+        }
+    }
 
-        if let Some(initializer) = res.read().unwrap().class.get(class.name.to_owned()) {
-            let mut initializer = initializer.to_owned();
-            initializer.data.this = Some(res.clone());
-            (initializer.call)(interpreter, &mut initializer.data, &arguments)?;
+    /// Sets the field named `name`, a private one among those `class` declared.
+    pub fn set_field(&mut self, class: Handle, name: String, value: TokenValue) {
+        match name.starts_with('#') {
+            true => self.privates.entry(class).or_default().define(name, value),
+            false => self.fields.define(name, value),
         }
+    }
 
-        Ok(res)
+    /// Every field with its value, private ones included.
+    pub fn all_fields(&self) -> impl Iterator<Item = (&String, &TokenValue)> {
+        self.fields
+            .iter()
+            .chain(self.privates.values().flat_map(Environment::iter))
     }
+}
 
-    pub fn get(&self, name: &Token) -> Option<TokenValue> {
-        if let Some(val) = self.fields.get(name) {
-            return Some(val);
-        }
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::{
+        Interpreter,
+        testing::{capture, run},
+    };
 
-        if name.lexeme == self.class.name {
-            return Some(TokenValue::Instance(self.this.clone().unwrap()));
-        }
+    #[test]
+    fn declares_fields_and_private_members() {
+        let source = "class Counter {
+  count = 0;
+  #step = 1;
+  label;
+  increment() { this.count = this.count + this.#next(); return this; }
+  #next() { return this.#step; }
+  set step(value) { this.#step = value; }
+}
+class Double < Counter {
+  tag = \"x\" + this.count;
+  peek() { return this.#step; }
+}
+var a = Counter();
+var b = Counter();
+a.step = 5;
+print(a.increment().increment().count);
+print(b.increment().count);
+print(b);
+print(Double().tag);
+";
+        assert_eq!(run(source), "10\n1\nCounter { count: 1, label: nil }\nx0\n");
 
-        if let Some(method) = self.class.get(name.lexeme.clone()) {
-            let mut method = method.to_owned();
-            method.data.this = self.this.clone();
-            return Some(TokenValue::Function(method));
-        }
+        let error = |strict: bool, script: &str| {
+            let mut interpreter = Interpreter::default();
+            interpreter.set_strict(strict);
+            capture(&mut interpreter, &format!("{}{}", source, script))
+                .unwrap_err()
+                .to_string()
+        };
+        let private = "Private member '#step' is only accessible inside its class";
+        assert!(error(false, "Counter().#step;").contains(private));
+        assert!(error(false, "Double().peek();").contains(private));
+        assert!(error(false, "Counter().#next();").contains("Private member '#next'"));
+        assert!(
+            error(true, "print(Counter().label);\nCounter().missing;")
+                .contains("[21:11] Interpreter Error: Undefined property 'missing'")
+        );
+    }
 
-        None
+    #[test]
+    fn keeps_private_fields_of_each_class_apart() {
+        let output = run("class T {
+  #s = 1;
+  getT() { return this.#s; }
+  setT(value) { this.#s = value; }
+}
+class U < T {
+  #s = 2;
+  getU() { return this.#s; }
+}
+var u = U();
+print(u.getT());
+print(u.getU());
+u.setT(3);
+print(u.getT());
+print(u.getU());
+print(u);
+");
+        assert_eq!(output, "1\n2\n3\n2\nU {}\n");
     }
 }
@@ -1,13 +1,11 @@
-use std::{
-    fmt::Display,
-    sync::{Arc, RwLock},
-};
+use std::fmt::Display;
 
-use crate::token::value::{class::Class, function::Function, instance::Instance};
+use crate::{memory::heap::Handle, token::value::function::Function};
 
 pub mod class;
 pub mod function;
 pub mod instance;
+pub mod traits;
 
 #[derive(Debug, Clone)]
 pub enum TokenValue {
@@ -15,11 +13,30 @@ pub enum TokenValue {
     Number(f64),
     String(String),
     Function(Function),
-    Class(Arc<Class>),
-    Instance(Arc<RwLock<Instance>>),
+    Class(Handle),
+    Instance(Handle),
+    Trait(Handle),
+    List(Handle),
     Nil,
 }
 
+impl TokenValue {
+    /// Name of the kind of value, as the `type` native reports it.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            TokenValue::Bool(_) => "bool",
+            TokenValue::Number(_) => "number",
+            TokenValue::String(_) => "string",
+            TokenValue::Function(_) => "function",
+            TokenValue::Class(_) => "class",
+            TokenValue::Instance(_) => "instance",
+            TokenValue::Trait(_) => "trait",
+            TokenValue::List(_) => "list",
+            TokenValue::Nil => "nil",
+        }
+    }
+}
+
 impl PartialEq for TokenValue {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
@@ -27,7 +44,11 @@ impl PartialEq for TokenValue {
             (TokenValue::Number(a), TokenValue::Number(b)) => a == b,
             (TokenValue::String(a), TokenValue::String(b)) => a == b,
             (TokenValue::Nil, TokenValue::Nil) => true,
-            (TokenValue::Function(a), TokenValue::Function(b)) => a.data.params == b.data.params,
+            (TokenValue::Function(a), TokenValue::Function(b)) => a == b,
+            (TokenValue::Class(a), TokenValue::Class(b)) => a == b,
+            (TokenValue::Instance(a), TokenValue::Instance(b)) => a == b,
+            (TokenValue::Trait(a), TokenValue::Trait(b)) => a == b,
+            (TokenValue::List(a), TokenValue::List(b)) => a == b,
             _ => false,
         }
     }
@@ -59,6 +80,8 @@ impl TryFrom<TokenValue> for bool {
             TokenValue::Nil => Ok(false),
             TokenValue::Function(_) => Err(anyhow::anyhow!("Cannot convert function to bool")),
             TokenValue::Class(_) => Err(anyhow::anyhow!("Cannot convert class to bool")),
+            TokenValue::Trait(_) => Err(anyhow::anyhow!("Cannot convert trait to bool")),
+            TokenValue::List(_) => Err(anyhow::anyhow!("Cannot convert list to bool")),
             TokenValue::Instance(_) => Err(anyhow::anyhow!("Cannot convert Instance to bool")),
         }
     }
@@ -114,6 +137,8 @@ impl TryFrom<TokenValue> for f64 {
             TokenValue::Nil => Err(anyhow::anyhow!("Cannot convert nil to f64")),
             TokenValue::Function(_) => Err(anyhow::anyhow!("Cannot convert function to f64")),
             TokenValue::Class(_) => Err(anyhow::anyhow!("Cannot convert class to f64")),
+            TokenValue::Trait(_) => Err(anyhow::anyhow!("Cannot convert trait to f64")),
+            TokenValue::List(_) => Err(anyhow::anyhow!("Cannot convert list to f64")),
             TokenValue::Instance(_) => Err(anyhow::anyhow!("Cannot convert Instance to f64")),
         }
     }
@@ -126,9 +151,22 @@ impl Display for TokenValue {
             TokenValue::Number(val) => val.to_string(),
             TokenValue::String(val) => val.to_owned(),
             TokenValue::Nil => String::from("nil"),
-            TokenValue::Function(_) => String::from("native function"),
-            TokenValue::Class(val) => val.name.to_string(),
-            TokenValue::Instance(val) => format!("{} instance", val.read().unwrap().class.name),
+            TokenValue::Function(function) => match function.data.body {
+                Some(_) => {
+                    let arity = match function.data.arity() {
+                        (min, Some(max)) if min == max => min.to_string(),
+                        (min, Some(max)) => format!("{}..{}", min, max),
+                        (min, None) => format!("{}..", min),
+                    };
+                    format!("<fn {}/{}>", function.data.name, arity)
+                }
+                None => format!("<native fn {}>", function.data.name),
+            },
+            // The names live on the heap, `Interpreter::stringify` resolves them.
+            TokenValue::Class(_) => String::from("<class>"),
+            TokenValue::Trait(_) => String::from("<trait>"),
+            TokenValue::List(_) => String::from("<list>"),
+            TokenValue::Instance(_) => String::from("<instance>"),
         };
         write!(f, "{}", text)
     }
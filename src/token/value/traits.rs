@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+
+use crate::token::value::function::Function;
+
+/// Named set of methods that classes compose with `with`.
+#[derive(Debug, Clone)]
+pub struct Trait {
+    pub name: String,
+    methods: HashMap<String, Function>,
+}
+
+impl Trait {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            methods: HashMap::new(),
+        }
+    }
+
+    pub fn define_method(&mut self, method: Function) {
+        self.methods.insert(method.data.name.to_owned(), method);
+    }
+
+    pub fn methods(&self) -> impl Iterator<Item = &Function> {
+        self.methods.values()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interpreter::testing::{fail, run};
+
+    #[test]
+    fn composes_traits_into_classes() {
+        let source = "trait Comparable {
+  less(other) { return this.key() < other.key(); }
+  describe() { return \"comparable\"; }
+}
+trait Printable {
+  show() { return \"<\" + this.key() + \">\"; }
+}
+trait Named {
+  describe() { return \"named\"; }
+}
+class Base {
+  describe() { return \"base\"; }
+}
+class Item < Base with Comparable, Printable {
+  init(key) { this.value = key; }
+  key() { return this.value; }
+}
+class Tag with Comparable, Named {
+  describe() { return \"tag\"; }
+}
+var a = Item(1);
+print(a.less(Item(2)));
+print(a.show());
+print(a.describe());
+print(Tag().describe());
+print(Comparable);
+print(implements(a, Comparable));
+print(implements(Item, Printable));
+print(implements(Tag(), Printable));
+";
+        assert_eq!(
+            run(source),
+            "true\n<1>\ncomparable\ntag\n<trait Comparable>\ntrue\ntrue\nfalse\n"
+        );
+
+        let error = fail(&format!("{}class Both with Comparable, Named {{}}", source));
+        assert!(error.contains(
+            "Method 'describe' is provided by both Comparable and Named, the class has to define it"
+        ));
+    }
+}
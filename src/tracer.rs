@@ -0,0 +1,196 @@
+use anyhow::Result;
+use log::{Level, debug, log_enabled, trace};
+
+use crate::{
+    interpreter::{Interpreter, hook::Hook},
+    parser::{
+        node::{Expression, statement::Statement},
+        printer::AstPrinter,
+    },
+    token::value::TokenValue,
+};
+
+/// Log target of the execution trace, statements are logged at `trace` level and calls at
+/// `debug` level so `RUST_LOG` can pick either.
+pub const TARGET: &str = "rotten::trace";
+
+/// Logs the statements and calls the interpreter runs, indented by call depth.
+#[derive(Debug, Default)]
+pub struct Tracer;
+
+impl Tracer {
+    fn indent(depth: usize) -> String {
+        "  ".repeat(depth)
+    }
+
+    fn describe(statement: &Statement) -> String {
+        let mut printer = AstPrinter;
+        let mut expression = |expr: &Expression| printer.expression(&mut expr.to_owned());
+
+        match statement {
+            Statement::Block { .. } => "{".to_string(),
+            Statement::Class { name, .. } => format!("class {}", name.lexeme),
+            Statement::Trait { name, .. } => format!("trait {}", name.lexeme),
+            Statement::Expression { expression: expr } => expression(expr),
+            Statement::Function { name, .. } => format!("fun {}", name.lexeme),
+            Statement::If { condition, .. } => format!("if {}", expression(condition)),
+            Statement::Return { value, .. } => match value {
+                Some(value) => format!("return {}", expression(value)),
+                None => "return".to_string(),
+            },
+            Statement::Var {
+                name, initializer, ..
+            } => match initializer {
+                Some(initializer) => format!("var {} = {}", name.lexeme, expression(initializer)),
+                None => format!("var {}", name.lexeme),
+            },
+            Statement::While { condition, .. } => format!("while {}", expression(condition)),
+        }
+    }
+
+    fn show(interpreter: &Interpreter, value: &TokenValue) -> String {
+        match value {
+            TokenValue::String(val) => format!("{:?}", val),
+            _ => interpreter.stringify(value),
+        }
+    }
+}
+
+impl Hook for Tracer {
+    fn on_statement(&mut self, interpreter: &mut Interpreter, statement: &Statement) -> Result<()> {
+        if !log_enabled!(target: TARGET, Level::Trace) {
+            return Ok(());
+        }
+        let Some(position) = statement.position() else {
+            return Ok(());
+        };
+
+        trace!(
+            target: TARGET,
+            "{}[{}:{}] {}",
+            Self::indent(interpreter.frames().len()),
+            position.row,
+            position.column,
+            Self::describe(statement)
+        );
+        Ok(())
+    }
+
+    fn on_call(&mut self, interpreter: &mut Interpreter, arguments: &[TokenValue]) -> Result<()> {
+        if !log_enabled!(target: TARGET, Level::Debug) {
+            return Ok(());
+        }
+        let frames = interpreter.frames();
+        let Some(frame) = frames.last() else {
+            return Ok(());
+        };
+
+        let arguments: Vec<String> = arguments
+            .iter()
+            .map(|argument| Self::show(interpreter, argument))
+            .collect();
+        let call_site = match &frame.call_site {
+            Some(position) => format!("[{}:{}] ", position.row, position.column),
+            None => String::new(),
+        };
+        debug!(
+            target: TARGET,
+            "{}{}call {}({})",
+            Self::indent(frames.len() - 1),
+            call_site,
+            frame.name,
+            arguments.join(", ")
+        );
+        Ok(())
+    }
+
+    fn on_return(&mut self, interpreter: &mut Interpreter, value: &TokenValue) -> Result<()> {
+        if !log_enabled!(target: TARGET, Level::Debug) {
+            return Ok(());
+        }
+        let frames = interpreter.frames();
+        let Some(frame) = frames.last() else {
+            return Ok(());
+        };
+
+        debug!(
+            target: TARGET,
+            "{}return {} -> {}",
+            Self::indent(frames.len() - 1),
+            frame.name,
+            Self::show(interpreter, value)
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use log::{LevelFilter, Log, Metadata, Record};
+
+    use super::*;
+    use crate::interpreter::testing::capture;
+
+    thread_local! {
+        static LINES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Keeps the trace records logged on the current thread, so tests running in parallel
+    /// don't see each other's lines.
+    struct Capture;
+
+    impl Log for Capture {
+        fn enabled(&self, metadata: &Metadata) -> bool {
+            metadata.target() == TARGET
+        }
+
+        fn log(&self, record: &Record) {
+            if self.enabled(record.metadata()) {
+                let line = format!("{:<5} {}", record.level(), record.args());
+                LINES.with(|lines| lines.borrow_mut().push(line));
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static CAPTURE: Capture = Capture;
+
+    fn trace(source: &str) -> Vec<String> {
+        // Another test may have installed the logger already, which is the same one.
+        let _ = log::set_logger(&CAPTURE);
+        log::set_max_level(LevelFilter::Trace);
+
+        let mut interpreter = Interpreter::default();
+        interpreter.attach(Box::new(Tracer));
+        capture(&mut interpreter, source).unwrap();
+
+        LINES.with(|lines| lines.take())
+    }
+
+    #[test]
+    fn logs_statements_calls_and_returns() {
+        let lines = trace(
+            "fun square(n) {
+  return n * n;
+}
+var a = square(3);
+print(\"a\");",
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "TRACE [1:5] fun square",
+                "TRACE [4:5] var a = (call square 3)",
+                "DEBUG [4:17] call square(3)",
+                "TRACE   [2:3] return (* n n)",
+                "DEBUG return square -> 9",
+                "TRACE [5:1] (call print \"a\")",
+                "DEBUG [5:10] call print(\"a\")",
+                "DEBUG return print -> nil",
+            ]
+        );
+    }
+}